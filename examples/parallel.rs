@@ -1,39 +1,58 @@
 use glam::Vec3;
 use monte_carlo_view_factors::{surfaces::Surface, Scene};
 
+use std::f32::consts::{PI, TAU};
 use std::time::Instant;
 
 fn main() {
     let mut scene = Scene::default();
 
     // Add +x face
-    scene.add_surface(
+    let small = scene.add_named_surface(
+        "small",
         Surface::new_disk(Vec3::X * 0.5, 1., -Vec3::X)
             .to_gray_body(0.2)
             .set_specular(),
     );
 
-    // Add -x face
-    scene.add_surface(
-        Surface::new_disk(-Vec3::X * 0.5, 3., Vec3::X)
-            .to_gray_body(0.2)
-            .set_specular(),
-    );
+    // Add -x face in two halves, which radiate as one node
+    let halves: Vec<_> = [(0., PI), (PI, TAU)]
+        .into_iter()
+        .map(|(theta_min, theta_max)| {
+            scene.add_surface(
+                Surface::new_disk(-Vec3::X * 0.5, 3., Vec3::X)
+                    .set_sweep(theta_min, theta_max, Vec3::Y)
+                    .to_gray_body(0.2)
+                    .set_specular(),
+            )
+        })
+        .collect();
+    let large = scene.add_group("large", &halves);
 
     let num_rays = 1_000_000;
 
     let start = Instant::now();
-    dbg!(scene.view_factors_for_surface(0, num_rays));
+    let view_factors = scene.view_factors_for_surface(small, num_rays);
     let duration = start.elapsed();
 
+    dbg!(view_factors.means());
+    dbg!(scene.view_factors_to_groups(&view_factors)[large.index()]);
     println!("Time elapsed computing view factors: {:?}", duration);
 
     let start = Instant::now();
-    dbg!(scene.view_factors_for_surface_parallel(0, num_rays));
+    let view_factors = scene.view_factors_for_surface_parallel(small, num_rays);
     let duration = start.elapsed();
 
+    dbg!(view_factors.means());
     println!(
         "Time elapsed computing view factors parallel: {:?}",
         duration
     );
+
+    let start = Instant::now();
+    let view_factors = scene.view_factors_for_group(large, num_rays);
+    let duration = start.elapsed();
+
+    dbg!(view_factors.means());
+    println!("Time elapsed computing group view factors: {:?}", duration);
 }
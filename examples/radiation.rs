@@ -5,7 +5,7 @@ fn main() {
     let mut scene = Scene::default();
 
     // Add +x face
-    scene.add_surface(
+    let small = scene.add_surface(
         Surface::new_disk(Vec3::X * 0.5, 1., -Vec3::X)
             .to_gray_body(0.2)
             .set_specular(),
@@ -18,5 +18,5 @@ fn main() {
             .set_specular(),
     );
 
-    scene.debug_rays_from_surface(0, 100);
+    scene.debug_rays_from_surface(small, 100);
 }
@@ -0,0 +1,298 @@
+use glam::Vec3;
+
+use super::{surfaces::Surface, HitRecord, Ray};
+
+/// Axis aligned bounding box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub const EMPTY: Aabb = Aabb {
+        min: Vec3::splat(f32::INFINITY),
+        max: Vec3::splat(f32::NEG_INFINITY),
+    };
+
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Self {
+        points.into_iter().fold(Aabb::EMPTY, |bounds, point| Aabb {
+            min: bounds.min.min(point),
+            max: bounds.max.max(point),
+        })
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Vec3::new(a.x, a.y, a.z),
+            Vec3::new(b.x, a.y, a.z),
+            Vec3::new(a.x, b.y, a.z),
+            Vec3::new(b.x, b.y, a.z),
+            Vec3::new(a.x, a.y, b.z),
+            Vec3::new(b.x, a.y, b.z),
+            Vec3::new(a.x, b.y, b.z),
+            Vec3::new(b.x, b.y, b.z),
+        ]
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let d = (self.max - self.min).max(Vec3::ZERO);
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    // Grow the box slightly so hits found with floating point error still lie inside it
+    pub fn padded(&self) -> Aabb {
+        let pad = Vec3::splat(1e-4 * (self.max - self.min).max_element() + 1e-6);
+        Aabb {
+            min: self.min - pad,
+            max: self.max + pad,
+        }
+    }
+
+    // Returns the entry distance of the ray if it crosses the box before t_max
+    fn intersect(&self, ray: &Ray, inverse_direction: Vec3, t_max: f32) -> Option<f32> {
+        let t0 = (self.min - ray.origin.0) * inverse_direction;
+        let t1 = (self.max - ray.origin.0) * inverse_direction;
+
+        let t_near = t0.min(t1).max_element().max(0.0);
+        let t_far = t0.max(t1).min_element().min(t_max);
+
+        (t_near <= t_far).then_some(t_near)
+    }
+}
+
+enum BvhNodeKind {
+    Leaf { first: usize, count: usize },
+    Interior { left: usize, right: usize },
+}
+
+struct BvhNode {
+    bounds: Aabb,
+    kind: BvhNodeKind,
+}
+
+/// Bounding volume hierarchy over the surfaces of a scene
+pub(crate) struct Bvh {
+    nodes: Vec<BvhNode>,
+    surface_ids: Vec<usize>,
+}
+
+// Build parameters for the binned surface area heuristic
+const NUM_BINS: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+const TRAVERSAL_COST: f32 = 1.0;
+const INTERSECTION_COST: f32 = 1.0;
+
+// Depth is capped so traversal can use a fixed size stack, which needs at most one slot per level
+const MAX_DEPTH: usize = 48;
+const STACK_SIZE: usize = MAX_DEPTH + 2;
+
+impl Bvh {
+    pub fn build(surfaces: &[Surface]) -> Self {
+        let bounds: Vec<Aabb> = surfaces.iter().map(|s| s.bounds().padded()).collect();
+
+        let mut bvh = Bvh {
+            nodes: Vec::new(),
+            surface_ids: (0..surfaces.len()).collect(),
+        };
+
+        if !surfaces.is_empty() {
+            bvh.build_node(&bounds, 0, surfaces.len(), 0);
+        }
+
+        bvh
+    }
+
+    // Recursively builds the node covering surface_ids[first..first + count], returning its index
+    fn build_node(&mut self, bounds: &[Aabb], first: usize, count: usize, depth: usize) -> usize {
+        let ids = &mut self.surface_ids[first..first + count];
+
+        let node_bounds = ids
+            .iter()
+            .fold(Aabb::EMPTY, |acc, &id| acc.union(&bounds[id]));
+        let centroid_bounds = Aabb::from_points(ids.iter().map(|&id| bounds[id].centroid()));
+
+        let node_index = self.nodes.len();
+        self.nodes.push(BvhNode {
+            bounds: node_bounds,
+            kind: BvhNodeKind::Leaf { first, count },
+        });
+
+        if count <= MAX_LEAF_SIZE || depth >= MAX_DEPTH {
+            return node_index;
+        }
+
+        let Some((axis, split)) = Self::find_split(bounds, ids, &node_bounds, &centroid_bounds)
+        else {
+            return node_index;
+        };
+
+        // Partition ids so surfaces left of the split come first
+        let extent = centroid_bounds.max[axis] - centroid_bounds.min[axis];
+        let bin_of = |id: usize| {
+            let offset = bounds[id].centroid()[axis] - centroid_bounds.min[axis];
+            ((offset / extent * NUM_BINS as f32) as usize).min(NUM_BINS - 1)
+        };
+
+        let mut left_count = 0;
+        for i in 0..count {
+            if bin_of(ids[i]) < split {
+                ids.swap(i, left_count);
+                left_count += 1;
+            }
+        }
+
+        let left = self.build_node(bounds, first, left_count, depth + 1);
+        let right = self.build_node(bounds, first + left_count, count - left_count, depth + 1);
+        self.nodes[node_index].kind = BvhNodeKind::Interior { left, right };
+
+        node_index
+    }
+
+    // Returns the axis and bin boundary with the lowest SAH cost, if it beats making a leaf
+    fn find_split(
+        bounds: &[Aabb],
+        ids: &[usize],
+        node_bounds: &Aabb,
+        centroid_bounds: &Aabb,
+    ) -> Option<(usize, usize)> {
+        let leaf_cost = INTERSECTION_COST * ids.len() as f32;
+        let mut best: Option<(f32, usize, usize)> = None;
+
+        for axis in 0..3 {
+            let extent = centroid_bounds.max[axis] - centroid_bounds.min[axis];
+            if extent <= 0.0 {
+                continue;
+            }
+
+            let mut bin_bounds = [Aabb::EMPTY; NUM_BINS];
+            let mut bin_counts = [0usize; NUM_BINS];
+            for &id in ids {
+                let offset = bounds[id].centroid()[axis] - centroid_bounds.min[axis];
+                let bin = ((offset / extent * NUM_BINS as f32) as usize).min(NUM_BINS - 1);
+                bin_bounds[bin] = bin_bounds[bin].union(&bounds[id]);
+                bin_counts[bin] += 1;
+            }
+
+            // Sweep from the right to get the bounds and counts of every right hand side
+            let mut right_areas = [0.0f32; NUM_BINS];
+            let mut right_counts = [0usize; NUM_BINS];
+            let mut accumulated = (Aabb::EMPTY, 0);
+            for bin in (1..NUM_BINS).rev() {
+                accumulated.0 = accumulated.0.union(&bin_bounds[bin]);
+                accumulated.1 += bin_counts[bin];
+                right_areas[bin] = accumulated.0.surface_area();
+                right_counts[bin] = accumulated.1;
+            }
+
+            let mut left = (Aabb::EMPTY, 0);
+            for split in 1..NUM_BINS {
+                left.0 = left.0.union(&bin_bounds[split - 1]);
+                left.1 += bin_counts[split - 1];
+
+                if left.1 == 0 || right_counts[split] == 0 {
+                    continue;
+                }
+
+                let cost = TRAVERSAL_COST
+                    + INTERSECTION_COST
+                        * (left.0.surface_area() * left.1 as f32
+                            + right_areas[split] * right_counts[split] as f32)
+                        / node_bounds.surface_area();
+
+                if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                    best = Some((cost, axis, split));
+                }
+            }
+        }
+
+        best.filter(|(cost, _, _)| *cost < leaf_cost)
+            .map(|(_, axis, split)| (axis, split))
+    }
+
+    // Finds the same closest hit as a brute force search, with ties going to the lowest surface id
+    pub fn cast_ray(&self, surfaces: &[Surface], ray: &Ray) -> Option<HitRecord> {
+        let inverse_direction = ray.direction.recip();
+        let mut closest_hit_opt: Option<HitRecord> = None;
+
+        // Stack of nodes to visit along with the distance the ray enters them
+        let mut stack = [(0usize, 0.0f32); STACK_SIZE];
+        let mut stack_len = 0;
+
+        if let Some(root) = self.nodes.first() {
+            if let Some(t_near) = root.bounds.intersect(ray, inverse_direction, f32::INFINITY) {
+                stack[0] = (0, t_near);
+                stack_len = 1;
+            }
+        }
+
+        while stack_len > 0 {
+            stack_len -= 1;
+            let (node_index, t_near) = stack[stack_len];
+
+            let t_max = closest_hit_opt
+                .as_ref()
+                .map_or(f32::INFINITY, |closest_hit| closest_hit.hit.t);
+
+            // A closer hit may have been found since this node was pushed
+            if t_near > t_max {
+                continue;
+            }
+
+            match self.nodes[node_index].kind {
+                BvhNodeKind::Leaf { first, count } => {
+                    for &surface_id in &self.surface_ids[first..first + count] {
+                        let Some(hit) = surfaces[surface_id].intersect(ray) else {
+                            continue;
+                        };
+
+                        let is_closer = closest_hit_opt.as_ref().is_none_or(|closest_hit| {
+                            hit.t < closest_hit.hit.t
+                                || (hit.t == closest_hit.hit.t
+                                    && surface_id < closest_hit.surface_id)
+                        });
+
+                        if is_closer {
+                            closest_hit_opt = Some(HitRecord::new(surface_id, hit, ray));
+                        }
+                    }
+                }
+                BvhNodeKind::Interior { left, right } => {
+                    let entry = |child: usize| {
+                        self.nodes[child]
+                            .bounds
+                            .intersect(ray, inverse_direction, t_max)
+                            .map(|t| (child, t))
+                    };
+
+                    // Push the farther child first so the nearer one is visited next
+                    let mut children = [entry(left), entry(right)];
+                    if let [Some((_, t_left)), Some((_, t_right))] = children {
+                        if t_left < t_right {
+                            children.swap(0, 1);
+                        }
+                    }
+
+                    for child in children.into_iter().flatten() {
+                        stack[stack_len] = child;
+                        stack_len += 1;
+                    }
+                }
+            }
+        }
+
+        closest_hit_opt
+    }
+}
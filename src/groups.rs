@@ -0,0 +1,28 @@
+/// Handle to a surface in a scene, which stays valid as more surfaces are added
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SurfaceId(pub(crate) usize);
+
+impl SurfaceId {
+    /// Position of the surface in `Scene::surfaces` and in every row of view factors
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// Handle to a group of surfaces in a scene
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GroupId(pub(crate) usize);
+
+impl GroupId {
+    /// Position of the group in `Scene::groups` and in group view factor results
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// Surfaces that radiate as a single thermal node
+#[derive(Clone, Debug)]
+pub struct SurfaceGroup {
+    pub name: String,
+    pub surfaces: Vec<SurfaceId>,
+}
@@ -1,16 +1,37 @@
+mod bvh;
+mod groups;
+mod linear;
 pub mod primitives;
+mod radks;
+mod reciprocity;
+mod sampling;
 pub mod scene;
 pub mod surfaces;
 pub mod tests;
+mod thermal;
 mod trace_record;
 pub mod transform;
-
+mod view_factors;
+
+pub use bvh::Aabb;
+pub use groups::{GroupId, SurfaceGroup, SurfaceId};
+pub use radks::{radks, RadkOptions};
+pub use reciprocity::{
+    correct_view_factors, CorrectedViewFactors, Imbalance, ReciprocityCorrection,
+};
+pub use sampling::Sampling;
 pub use scene::Scene;
+pub use thermal::{
+    Integrator, SolverOptions, SteadyState, TemperatureHistory, ThermalError, ThermalNetwork,
+    TransientOptions, STEFAN_BOLTZMANN,
+};
 pub use trace_record::TraceRecord;
+pub use view_factors::{ConvergenceTarget, ViewFactorEstimate, ViewFactors};
 
 use glam::f32::Vec3;
 use kiss3d::window::Window;
-use rand::{thread_rng, Rng};
+use rand::Rng;
+use surfaces::Side;
 
 #[derive(Clone, Debug)]
 pub struct Point(pub Vec3);
@@ -53,8 +74,7 @@ impl Ray {
 }
 
 impl Normal {
-    pub fn to_diffuse_ray(&self) -> Vec3 {
-        let mut rng = thread_rng();
+    pub fn to_diffuse_ray(&self, rng: &mut impl Rng) -> Vec3 {
         let g = rng.gen_range(0.0..1.0f32).sqrt().asin();
         let phi = rng.gen_range(0.0..std::f32::consts::TAU);
 
@@ -67,6 +87,30 @@ impl Normal {
 
         Vec3 { x, y, z }
     }
+
+    // Mirror reflection of an incoming direction
+    pub fn to_specular_ray(&self, direction: Vec3) -> Vec3 {
+        direction - 2. * direction.dot(self.0) * self.0
+    }
+
+    // Snell's law for an incoming direction against the normal, where relative_index is the
+    // refractive index of the incoming side divided by that of the outgoing side. Returns None on
+    // total internal reflection.
+    pub fn to_refracted_ray(&self, direction: Vec3, relative_index: f32) -> Option<Vec3> {
+        let direction = direction.normalize();
+        let cos_incident = -direction.dot(self.0);
+        let sin_squared_transmitted =
+            relative_index * relative_index * (1. - cos_incident * cos_incident);
+
+        if sin_squared_transmitted > 1. {
+            return None;
+        }
+
+        let cos_transmitted = (1. - sin_squared_transmitted).sqrt();
+        Some(
+            relative_index * direction + (relative_index * cos_incident - cos_transmitted) * self.0,
+        )
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -79,6 +123,32 @@ pub struct SurfaceSample {
 pub struct HitRecord {
     pub hit: Hit,
     pub surface_id: usize,
+    pub side: Side,
+}
+
+impl HitRecord {
+    pub fn new(surface_id: usize, hit: Hit, ray: &Ray) -> Self {
+        // The ray travels against the normal when it hits the front
+        let side = if ray.direction.dot(hit.normal.0) <= 0.0 {
+            Side::Front
+        } else {
+            Side::Back
+        };
+
+        Self {
+            hit,
+            surface_id,
+            side,
+        }
+    }
+
+    // Normal of the side that was hit, pointing back towards where the ray came from
+    pub fn facing_normal(&self) -> Normal {
+        match self.side {
+            Side::Front => self.hit.normal.clone(),
+            Side::Back => Normal::new(-self.hit.normal.0),
+        }
+    }
 }
 
 pub trait DebugRender {
@@ -0,0 +1,59 @@
+// Solution of a square linear system
+pub(crate) struct LinearSolution {
+    pub values: Vec<f64>,
+    // Unknowns the system does not determine, which are given a value of zero
+    pub undetermined: Vec<usize>,
+}
+
+// Pivots smaller than this fraction of the largest coefficient their row started with count as
+// zero, so rows that only differ from others by rounding are left without a pivot
+const PIVOT_TOLERANCE: f64 = 1e-12;
+
+// Gaussian elimination with partial pivoting on an augmented matrix, with each row holding the
+// coefficients followed by the right hand side
+pub(crate) fn solve_linear_system(mut system: Vec<Vec<f64>>) -> LinearSolution {
+    let n = system.len();
+    let mut scales: Vec<f64> = system
+        .iter()
+        .map(|row| row[..n].iter().map(|value| value.abs()).fold(0.0, f64::max))
+        .collect();
+    let mut pivots = vec![None; n];
+
+    let mut row = 0;
+    for column in 0..n {
+        let Some(pivot) =
+            (row..n).max_by(|&a, &b| system[a][column].abs().total_cmp(&system[b][column].abs()))
+        else {
+            break;
+        };
+
+        if system[pivot][column].abs() <= PIVOT_TOLERANCE * scales[pivot] {
+            continue;
+        }
+
+        system.swap(row, pivot);
+        scales.swap(row, pivot);
+        let pivot_row = system[row].clone();
+        for (other, other_row) in system.iter_mut().enumerate() {
+            let factor = other_row[column] / pivot_row[column];
+            if other != row && factor != 0.0 {
+                for (value, pivot_value) in other_row[column..].iter_mut().zip(&pivot_row[column..])
+                {
+                    *value -= factor * pivot_value;
+                }
+            }
+        }
+
+        pivots[column] = Some(row);
+        row += 1;
+    }
+
+    LinearSolution {
+        values: pivots
+            .iter()
+            .enumerate()
+            .map(|(column, pivot)| pivot.map_or(0.0, |row| system[row][n] / system[row][column]))
+            .collect(),
+        undetermined: (0..n).filter(|&column| pivots[column].is_none()).collect(),
+    }
+}
@@ -0,0 +1,332 @@
+mod obj;
+mod stl;
+
+pub use stl::StlOrientation;
+
+use std::{collections::HashMap, fmt, io, path::Path};
+
+use glam::Vec3;
+
+use super::Primitive;
+use crate::{surfaces::Surface, GroupId, Scene, SurfaceId};
+
+// Relative tolerance used when deciding whether a quad is a flat parallelogram
+const PARALLELOGRAM_TOLERANCE: f32 = 1e-5;
+
+// Relative tolerance on the area covered by a face's triangles
+const TRIANGULATION_TOLERANCE: f32 = 1e-4;
+
+// Triangles with less area than this fraction of their longest edge squared are treated as having
+// none, since their surfaces would have singular transforms
+const DEGENERATE_TOLERANCE: f32 = 1e-6;
+
+/// Selects how the faces of a mesh map onto logical radiation nodes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MeshNodes {
+    /// Every face is its own node
+    #[default]
+    Faces,
+    /// Faces sharing a group (`g`/`o` in OBJ files, `solid` in ASCII STL files) are merged into one node
+    Groups,
+    /// Faces sharing a material (`usemtl` in OBJ files) are merged into one node
+    Materials,
+}
+
+/// A logical radiation node made up of one or more mesh surfaces
+#[derive(Clone, Debug)]
+pub struct MeshNode {
+    /// Name of the face, group or material, after the mesh's name if it has one
+    pub name: String,
+    /// Positions of the node's surfaces in the handles returned when the mesh is added to a scene
+    pub surfaces: Vec<usize>,
+}
+
+impl MeshNode {
+    /// Groups the node's surfaces in a scene, given the handles returned by `Scene::add_primitive`
+    pub fn add_group(&self, scene: &mut Scene, surfaces: &[SurfaceId]) -> GroupId {
+        let members: Vec<SurfaceId> = self.surfaces.iter().map(|&i| surfaces[i]).collect();
+        scene.add_group(&self.name, &members)
+    }
+}
+
+#[derive(Debug)]
+pub enum MeshError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+    InvalidBinary(String),
+    Orientation { facet: usize },
+    DegenerateFacet { facet: usize },
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshError::Io(error) => write!(f, "failed to read mesh: {error}"),
+            MeshError::Parse { line, message } => {
+                write!(f, "failed to parse mesh on line {line}: {message}")
+            }
+            MeshError::InvalidBinary(message) => write!(f, "invalid binary mesh: {message}"),
+            MeshError::Orientation { facet } => {
+                write!(
+                    f,
+                    "facet {facet} has a normal that disagrees with its winding"
+                )
+            }
+            MeshError::DegenerateFacet { facet } => {
+                write!(f, "facet {facet} has no area")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MeshError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MeshError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MeshError {
+    fn from(error: io::Error) -> Self {
+        MeshError::Io(error)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct MeshFace {
+    // Indices into the mesh vertices, wound counter-clockwise about the face normal
+    vertices: Vec<usize>,
+    group: Option<usize>,
+    material: Option<usize>,
+}
+
+/// Polygon mesh where each face becomes a triangle or parallelogram surface
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+    vertices: Vec<Vec3>,
+    faces: Vec<MeshFace>,
+    groups: Vec<String>,
+    materials: Vec<String>,
+    node_mode: MeshNodes,
+    name: Option<String>,
+}
+
+impl Mesh {
+    pub fn with_nodes(mut self, node_mode: MeshNodes) -> Self {
+        self.node_mode = node_mode;
+        self
+    }
+
+    /// Names the mesh, which prefixes the names of its nodes so that several meshes can be grouped
+    /// in one scene. Meshes read from a file are named after it.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    // Index of a group or material name, adding it the first time it appears
+    fn intern(names: &mut Vec<String>, ids: &mut HashMap<String, usize>, name: String) -> usize {
+        *ids.entry(name.clone()).or_insert_with(|| {
+            names.push(name);
+            names.len() - 1
+        })
+    }
+
+    fn named_after(self, path: &Path) -> Self {
+        match path.file_stem() {
+            Some(stem) => self.with_name(&stem.to_string_lossy()),
+            None => self,
+        }
+    }
+
+    pub fn num_faces(&self) -> usize {
+        self.faces.len()
+    }
+
+    /// Radiation nodes in the order the mesh adds surfaces to a scene
+    pub fn nodes(&self) -> Vec<MeshNode> {
+        let mut nodes: Vec<MeshNode> = Vec::new();
+        let mut node_for_key = HashMap::new();
+
+        for (offset, (face_index, _)) in self.face_surfaces().iter().enumerate() {
+            let face = &self.faces[*face_index];
+            let (key, name) = match self.node_mode {
+                MeshNodes::Faces => (Some(*face_index), format!("face_{face_index}")),
+                MeshNodes::Groups => (face.group, Self::name_or_default(&self.groups, face.group)),
+                MeshNodes::Materials => (
+                    face.material,
+                    Self::name_or_default(&self.materials, face.material),
+                ),
+            };
+
+            let node = *node_for_key.entry(key).or_insert_with(|| {
+                let name = match &self.name {
+                    Some(mesh) => format!("{mesh}/{name}"),
+                    None => name,
+                };
+                nodes.push(MeshNode {
+                    name,
+                    surfaces: Vec::new(),
+                });
+                nodes.len() - 1
+            });
+            nodes[node].surfaces.push(offset);
+        }
+
+        nodes
+    }
+
+    fn name_or_default(names: &[String], index: Option<usize>) -> String {
+        index.map_or_else(|| "default".to_string(), |i| names[i].clone())
+    }
+
+    // Surfaces for every face, tagged with the index of the face they came from
+    fn face_surfaces(&self) -> Vec<(usize, Surface)> {
+        let mut surfaces = Vec::new();
+
+        for (face_index, face) in self.faces.iter().enumerate() {
+            let corners: Vec<Vec3> = face.vertices.iter().map(|&v| self.vertices[v]).collect();
+
+            if let Some(surface) = Self::parallelogram(&corners) {
+                surfaces.push((face_index, surface));
+                continue;
+            }
+
+            // Triangulate everything else, which was checked to be possible when parsing
+            let triangles = triangulate(&corners).expect("Face is not a simple polygon");
+            for [a, b, c] in triangles {
+                surfaces.push((
+                    face_index,
+                    Surface::new_triangle(corners[a], corners[b], corners[c]),
+                ));
+            }
+        }
+
+        surfaces
+    }
+
+    // Quads that are flat parallelograms can be represented by a single surface
+    fn parallelogram(corners: &[Vec3]) -> Option<Surface> {
+        let [a, b, c, d] = corners else {
+            return None;
+        };
+
+        let edge_1 = *b - *a;
+        let edge_2 = *d - *a;
+        let scale = edge_1.length().max(edge_2.length());
+
+        let is_parallelogram = (*c - (*b + edge_2)).length() <= PARALLELOGRAM_TOLERANCE * scale;
+
+        (is_parallelogram && !is_degenerate(*a, *b, *d))
+            .then(|| Surface::new_parallelogram(*a, edge_1, edge_2))
+    }
+}
+
+impl Primitive for Mesh {
+    fn add_surfaces(&self, scene: &mut Scene) -> Vec<SurfaceId> {
+        self.face_surfaces()
+            .into_iter()
+            .map(|(_, surface)| scene.add_surface(surface))
+            .collect()
+    }
+}
+
+// Whether a triangle has too little area for a surface
+fn is_degenerate(a: Vec3, b: Vec3, c: Vec3) -> bool {
+    let longest_edge = [b - a, c - b, a - c]
+        .iter()
+        .map(|edge| edge.length_squared())
+        .fold(0.0, f32::max);
+    (b - a).cross(c - a).length() <= DEGENERATE_TOLERANCE * longest_edge
+}
+
+// Splits a simple polygon into triangles by ear clipping, dropping slivers with no area. Convex
+// polygons give the same triangles as a fan from the first corner. Returns None for polygons that
+// intersect themselves.
+fn triangulate(corners: &[Vec3]) -> Option<Vec<[usize; 3]>> {
+    // Newell's method gives a normal that follows the winding, even for non-convex polygons
+    let normal = (0..corners.len()).fold(Vec3::ZERO, |normal, i| {
+        let (p, q) = (corners[i], corners[(i + 1) % corners.len()]);
+        normal
+            + Vec3::new(
+                (p.y - q.y) * (p.z + q.z),
+                (p.z - q.z) * (p.x + q.x),
+                (p.x - q.x) * (p.y + q.y),
+            )
+    });
+
+    // Without a normal the polygon is either a line, which has no area, or crosses itself
+    let is_line = (0..corners.len()).all(|i| {
+        let (a, b, c) = (
+            corners[i],
+            corners[(i + 1) % corners.len()],
+            corners[(i + 2) % corners.len()],
+        );
+        (b - a).cross(c - b) == Vec3::ZERO
+    });
+    if normal == Vec3::ZERO && !is_line {
+        return None;
+    }
+
+    let turn = |a: Vec3, b: Vec3, c: Vec3| (b - a).cross(c - b).dot(normal);
+
+    let mut remaining: Vec<usize> = (0..corners.len()).collect();
+    let mut triangles = Vec::new();
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let corner = |i: usize| corners[remaining[i % n]];
+
+        // Try ears in fan order, so clipping starts with the triangle at the first corner
+        let ear = (1..=n).map(|i| i % n).find(|&i| {
+            let (a, b, c) = (corner(i + n - 1), corner(i), corner(i + 1));
+            turn(a, b, c) > 0.0
+                && (0..n)
+                    .map(corner)
+                    .filter(|&p| p != a && p != b && p != c)
+                    .all(|p| {
+                        !(turn(a, b, p) >= 0.0 && turn(b, c, p) >= 0.0 && turn(c, a, p) >= 0.0)
+                    })
+        });
+
+        match ear {
+            Some(i) => {
+                triangles.push([
+                    remaining[(i + n - 1) % n],
+                    remaining[i],
+                    remaining[(i + 1) % n],
+                ]);
+                remaining.remove(i);
+            }
+            // Corners that do not turn only add slivers, otherwise the polygon crosses itself
+            None => {
+                let straight =
+                    (0..n).find(|&i| turn(corner(i + n - 1), corner(i), corner(i + 1)) == 0.0)?;
+                remaining.remove(straight);
+            }
+        }
+    }
+
+    let [a, b, c] = [remaining[0], remaining[1], remaining[2]];
+    if turn(corners[a], corners[b], corners[c]) > 0.0 {
+        triangles.push([a, b, c]);
+    }
+    triangles.retain(|&[a, b, c]| !is_degenerate(corners[a], corners[b], corners[c]));
+
+    // Triangles of a polygon that crosses itself cover more than its signed area
+    let area: f32 = triangles
+        .iter()
+        .map(|&[a, b, c]| {
+            (corners[b] - corners[a])
+                .cross(corners[c] - corners[a])
+                .length()
+        })
+        .sum();
+    let scale = corners
+        .iter()
+        .map(|p| (*p - corners[0]).length_squared())
+        .fold(0.0, f32::max);
+    (area - normal.length() <= TRIANGULATION_TOLERANCE * scale).then_some(triangles)
+}
@@ -0,0 +1,110 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use glam::Vec3;
+
+use super::{triangulate, Mesh, MeshError, MeshFace};
+
+// Wavefront OBJ importing
+impl Mesh {
+    pub fn from_obj(path: impl AsRef<Path>) -> Result<Self, MeshError> {
+        let mesh = Self::parse_obj(&fs::read_to_string(&path)?)?;
+        Ok(mesh.named_after(path.as_ref()))
+    }
+
+    pub fn parse_obj(source: &str) -> Result<Self, MeshError> {
+        let mut mesh = Mesh::default();
+
+        let mut group_ids = HashMap::new();
+        let mut material_ids = HashMap::new();
+        let mut group = None;
+        let mut material = None;
+
+        for (line_index, line) in source.lines().enumerate() {
+            let line_number = line_index + 1;
+            let parse_error = |message: String| MeshError::Parse {
+                line: line_number,
+                message,
+            };
+
+            // Strip comments
+            let line = line.split('#').next().unwrap_or_default();
+            let mut tokens = line.split_whitespace();
+
+            match tokens.next() {
+                Some("v") => {
+                    let mut coordinates = [0.0f32; 3];
+                    for coordinate in coordinates.iter_mut() {
+                        let token = tokens.next().ok_or_else(|| {
+                            parse_error("vertex has fewer than 3 coordinates".into())
+                        })?;
+                        *coordinate = token
+                            .parse()
+                            .map_err(|_| parse_error(format!("invalid coordinate '{token}'")))?;
+                    }
+                    mesh.vertices.push(Vec3::from_array(coordinates));
+                }
+                Some("f") => {
+                    let vertices = tokens
+                        .map(|token| Self::parse_obj_index(token, mesh.vertices.len()))
+                        .collect::<Result<Vec<usize>, String>>()
+                        .map_err(parse_error)?;
+
+                    if vertices.len() < 3 {
+                        return Err(parse_error("face has fewer than 3 vertices".into()));
+                    }
+
+                    // Faces left without a triangle of any size have no area, like degenerate STL
+                    // facets
+                    let corners: Vec<Vec3> = vertices.iter().map(|&v| mesh.vertices[v]).collect();
+                    match triangulate(&corners) {
+                        None => return Err(parse_error("face intersects itself".into())),
+                        Some(triangles) if triangles.is_empty() => {
+                            return Err(MeshError::DegenerateFacet {
+                                facet: mesh.faces.len(),
+                            });
+                        }
+                        Some(_) => {}
+                    }
+
+                    mesh.faces.push(MeshFace {
+                        vertices,
+                        group,
+                        material,
+                    });
+                }
+                Some("g") | Some("o") => {
+                    let name = tokens.collect::<Vec<_>>().join(" ");
+                    group = Some(Self::intern(&mut mesh.groups, &mut group_ids, name));
+                }
+                Some("usemtl") => {
+                    let name = tokens.collect::<Vec<_>>().join(" ");
+                    material = Some(Self::intern(&mut mesh.materials, &mut material_ids, name));
+                }
+                // Texture coordinates, vertex normals, smoothing groups, lines, etc. are not needed
+                _ => {}
+            }
+        }
+
+        Ok(mesh)
+    }
+
+    // Face vertices look like "v", "v/vt", "v//vn", or "v/vt/vn" and are 1-based or negative (relative)
+    fn parse_obj_index(token: &str, num_vertices: usize) -> Result<usize, String> {
+        let index_token = token.split('/').next().unwrap_or_default();
+        let index: i64 = index_token
+            .parse()
+            .map_err(|_| format!("invalid face vertex '{token}'"))?;
+
+        let resolved = match index {
+            i if i > 0 => i - 1,
+            i if i < 0 => num_vertices as i64 + i,
+            _ => return Err("face vertex index 0 is not valid".into()),
+        };
+
+        if resolved < 0 || resolved >= num_vertices as i64 {
+            return Err(format!("face vertex '{token}' refers to a missing vertex"));
+        }
+
+        Ok(resolved as usize)
+    }
+}
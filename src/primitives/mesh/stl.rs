@@ -0,0 +1,194 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use glam::Vec3;
+
+use super::{is_degenerate, Mesh, MeshError, MeshFace};
+
+/// Resolves disagreements between an STL facet's stored normal and its vertex winding
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StlOrientation {
+    /// Flip the winding of facets whose stored normal points the other way
+    #[default]
+    FromNormals,
+    /// Ignore stored normals and trust the counter-clockwise winding
+    FromWinding,
+    /// Fail on any facet whose stored normal disagrees with its winding
+    Strict,
+}
+
+// Header plus facet count
+const BINARY_HEADER_SIZE: usize = 84;
+// Normal, three vertices, and attribute byte count
+const BINARY_FACET_SIZE: usize = 50;
+
+// STL importing
+impl Mesh {
+    pub fn from_stl(
+        path: impl AsRef<Path>,
+        orientation: StlOrientation,
+    ) -> Result<Self, MeshError> {
+        let mesh = Self::parse_stl(&fs::read(&path)?, orientation)?;
+        Ok(mesh.named_after(path.as_ref()))
+    }
+
+    /// Parses either ASCII or binary STL data
+    pub fn parse_stl(bytes: &[u8], orientation: StlOrientation) -> Result<Self, MeshError> {
+        // Some exporters start binary headers with "solid" too, so check the binary size first
+        let is_binary = bytes.len() >= BINARY_HEADER_SIZE && {
+            let count = u32::from_le_bytes(bytes[80..84].try_into().unwrap()) as usize;
+            count
+                .checked_mul(BINARY_FACET_SIZE)
+                .and_then(|size| size.checked_add(BINARY_HEADER_SIZE))
+                == Some(bytes.len())
+        };
+
+        if is_binary {
+            Self::parse_binary_stl(bytes, orientation)
+        } else if bytes.trim_ascii_start().starts_with(b"solid") {
+            let source = std::str::from_utf8(bytes).map_err(|error| {
+                let valid = &bytes[..error.valid_up_to()];
+                MeshError::Parse {
+                    line: valid.iter().filter(|&&byte| byte == b'\n').count() + 1,
+                    message: "ASCII STL is not valid UTF-8".into(),
+                }
+            })?;
+            Self::parse_ascii_stl(source, orientation)
+        } else {
+            Err(MeshError::InvalidBinary(format!(
+                "binary STL size of {} bytes does not match its facet count",
+                bytes.len()
+            )))
+        }
+    }
+
+    fn parse_binary_stl(bytes: &[u8], orientation: StlOrientation) -> Result<Self, MeshError> {
+        let mut mesh = Mesh::default();
+
+        let read_vec3 = |offset: usize| {
+            let mut components = [0.0f32; 3];
+            for (i, component) in components.iter_mut().enumerate() {
+                let start = offset + 4 * i;
+                *component = f32::from_le_bytes(bytes[start..start + 4].try_into().unwrap());
+            }
+            Vec3::from_array(components)
+        };
+
+        for (facet, offset) in (BINARY_HEADER_SIZE..bytes.len())
+            .step_by(BINARY_FACET_SIZE)
+            .enumerate()
+        {
+            let normal = read_vec3(offset);
+            let corners = [
+                read_vec3(offset + 12),
+                read_vec3(offset + 24),
+                read_vec3(offset + 36),
+            ];
+            mesh.add_stl_facet(facet, normal, corners, None, orientation)?;
+        }
+
+        Ok(mesh)
+    }
+
+    fn parse_ascii_stl(source: &str, orientation: StlOrientation) -> Result<Self, MeshError> {
+        let mut mesh = Mesh::default();
+
+        let mut group_ids = HashMap::new();
+        let mut group = None;
+        let mut facet = 0;
+        let mut normal = Vec3::ZERO;
+        let mut corners = Vec::with_capacity(3);
+
+        for (line_index, line) in source.lines().enumerate() {
+            let parse_error = |message: String| MeshError::Parse {
+                line: line_index + 1,
+                message,
+            };
+
+            let mut tokens = line.split_whitespace();
+
+            match tokens.next() {
+                // Solids that share a name are one group
+                Some("solid") => {
+                    let name = tokens.collect::<Vec<_>>().join(" ");
+                    group = Some(Self::intern(&mut mesh.groups, &mut group_ids, name));
+                }
+                Some("facet") => {
+                    if tokens.next() != Some("normal") {
+                        return Err(parse_error("expected 'facet normal'".into()));
+                    }
+                    normal = Self::parse_stl_vec3(tokens).map_err(parse_error)?;
+                    corners.clear();
+                }
+                Some("vertex") => {
+                    corners.push(Self::parse_stl_vec3(tokens).map_err(parse_error)?);
+                }
+                Some("endfacet") => {
+                    let corners: [Vec3; 3] = corners
+                        .as_slice()
+                        .try_into()
+                        .map_err(|_| parse_error("facet does not have 3 vertices".into()))?;
+                    mesh.add_stl_facet(facet, normal, corners, group, orientation)?;
+                    facet += 1;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(mesh)
+    }
+
+    fn parse_stl_vec3<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Result<Vec3, String> {
+        let mut components = [0.0f32; 3];
+        for component in components.iter_mut() {
+            let token = tokens.next().ok_or("expected 3 components")?;
+            *component = token
+                .parse()
+                .map_err(|_| format!("invalid number '{token}'"))?;
+        }
+        Ok(Vec3::from_array(components))
+    }
+
+    fn add_stl_facet(
+        &mut self,
+        facet: usize,
+        normal: Vec3,
+        corners: [Vec3; 3],
+        group: Option<usize>,
+        orientation: StlOrientation,
+    ) -> Result<(), MeshError> {
+        let [a, b, c] = corners;
+        if is_degenerate(a, b, c) {
+            return Err(MeshError::DegenerateFacet { facet });
+        }
+        let winding_normal = (b - a).cross(c - a);
+
+        // Zero normals are common in STL files and mean the winding should be used
+        let disagrees = normal != Vec3::ZERO && winding_normal.dot(normal) < 0.0;
+
+        let flip = match orientation {
+            StlOrientation::FromNormals => disagrees,
+            StlOrientation::FromWinding => false,
+            StlOrientation::Strict if disagrees => {
+                return Err(MeshError::Orientation { facet });
+            }
+            StlOrientation::Strict => false,
+        };
+
+        let first = self.vertices.len();
+        self.vertices.extend(corners);
+
+        let vertices = if flip {
+            vec![first, first + 2, first + 1]
+        } else {
+            vec![first, first + 1, first + 2]
+        };
+
+        self.faces.push(MeshFace {
+            vertices,
+            group,
+            material: None,
+        });
+
+        Ok(())
+    }
+}
@@ -1,9 +1,14 @@
+mod mesh;
+
+pub use mesh::{Mesh, MeshError, MeshNode, MeshNodes, StlOrientation};
+
 use glam::Vec3;
 
-use super::{surfaces::Surface, Scene};
+use super::{surfaces::Surface, Scene, SurfaceId};
 
 pub trait Primitive {
-    fn add_surfaces(&self, scene: &mut Scene);
+    // Adds the primitive's surfaces to the scene, returning them in the order they were added
+    fn add_surfaces(&self, scene: &mut Scene) -> Vec<SurfaceId>;
 }
 
 pub struct Cube {
@@ -20,6 +25,15 @@ pub struct Cylinder {
     pub radius: f32,
 }
 
+// Tube with walls between the inner and outer radius, such as a pipe or baffle
+pub struct HollowCylinder {
+    pub translation: Vec3,
+    pub axis: Vec3,
+    pub height: f32,
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+}
+
 pub struct Cone {
     pub translation: Vec3,
     pub axis: Vec3,
@@ -28,108 +42,141 @@ pub struct Cone {
 }
 
 impl Primitive for Cube {
-    fn add_surfaces(&self, scene: &mut Scene) {
+    fn add_surfaces(&self, scene: &mut Scene) -> Vec<SurfaceId> {
         let i = self.axis_x;
         let j = self.axis_y;
         let k = i.cross(j).normalize();
 
-        // Add +x face
-        scene.add_surface(Surface::new_rectangle(
-            self.translation + i * self.scale.x / 2.,
-            self.scale.y,
-            self.scale.z,
-            i,
-            j,
-        ));
-
-        // Add -x face
-        scene.add_surface(Surface::new_rectangle(
-            self.translation - i * self.scale.x / 2.,
-            self.scale.y,
-            self.scale.z,
-            -i,
-            j,
-        ));
-
-        // Add +y face
-        scene.add_surface(Surface::new_rectangle(
-            self.translation + j * self.scale.y / 2.,
-            self.scale.x,
-            self.scale.z,
-            j,
-            i,
-        ));
-
-        // Add -y face
-        scene.add_surface(Surface::new_rectangle(
-            self.translation - j * self.scale.y / 2.,
-            self.scale.x,
-            self.scale.z,
-            -j,
-            i,
-        ));
-
-        // Add +z face
-        scene.add_surface(Surface::new_rectangle(
-            self.translation + k * self.scale.z / 2.,
-            self.scale.x,
-            self.scale.y,
-            k,
-            i,
-        ));
-
-        // Add -z face
-        scene.add_surface(Surface::new_rectangle(
-            self.translation - k * self.scale.z / 2.,
-            self.scale.x,
-            self.scale.y,
-            -k,
-            -i,
-        ));
+        vec![
+            // Add +x face
+            scene.add_surface(Surface::new_rectangle(
+                self.translation + i * self.scale.x / 2.,
+                self.scale.y,
+                self.scale.z,
+                i,
+                j,
+            )),
+            // Add -x face
+            scene.add_surface(Surface::new_rectangle(
+                self.translation - i * self.scale.x / 2.,
+                self.scale.y,
+                self.scale.z,
+                -i,
+                j,
+            )),
+            // Add +y face
+            scene.add_surface(Surface::new_rectangle(
+                self.translation + j * self.scale.y / 2.,
+                self.scale.x,
+                self.scale.z,
+                j,
+                i,
+            )),
+            // Add -y face
+            scene.add_surface(Surface::new_rectangle(
+                self.translation - j * self.scale.y / 2.,
+                self.scale.x,
+                self.scale.z,
+                -j,
+                i,
+            )),
+            // Add +z face
+            scene.add_surface(Surface::new_rectangle(
+                self.translation + k * self.scale.z / 2.,
+                self.scale.x,
+                self.scale.y,
+                k,
+                i,
+            )),
+            // Add -z face
+            scene.add_surface(Surface::new_rectangle(
+                self.translation - k * self.scale.z / 2.,
+                self.scale.x,
+                self.scale.y,
+                -k,
+                -i,
+            )),
+        ]
     }
 }
 
 impl Primitive for Cylinder {
-    fn add_surfaces(&self, scene: &mut Scene) {
+    fn add_surfaces(&self, scene: &mut Scene) -> Vec<SurfaceId> {
         let axis = self.axis.normalize();
 
-        // Add top face
-        scene.add_surface(Surface::new_disk(
-            self.translation + axis * self.height / 2.,
-            self.radius,
-            axis,
-        ));
-
-        // Add bottom face
-        scene.add_surface(Surface::new_disk(
-            self.translation - axis * self.height / 2.,
-            self.radius,
-            -axis,
-        ));
-
-        // Add side face
-        scene.add_surface(Surface::new_cylinder(
-            self.translation,
-            self.radius,
-            self.height,
-            axis,
-        ));
+        vec![
+            // Add top face
+            scene.add_surface(Surface::new_disk(
+                self.translation + axis * self.height / 2.,
+                self.radius,
+                axis,
+            )),
+            // Add bottom face
+            scene.add_surface(Surface::new_disk(
+                self.translation - axis * self.height / 2.,
+                self.radius,
+                -axis,
+            )),
+            // Add side face
+            scene.add_surface(Surface::new_cylinder(
+                self.translation,
+                self.radius,
+                self.height,
+                axis,
+            )),
+        ]
     }
 }
 
-impl Primitive for Cone {
-    fn add_surfaces(&self, scene: &mut Scene) {
+impl Primitive for HollowCylinder {
+    fn add_surfaces(&self, scene: &mut Scene) -> Vec<SurfaceId> {
         let axis = self.axis.normalize();
 
-        // Add base face
-        scene.add_surface(Surface::new_disk(self.translation, self.radius, -axis));
+        vec![
+            // Add top face
+            scene.add_surface(Surface::new_annulus(
+                self.translation + axis * self.height / 2.,
+                self.inner_radius,
+                self.outer_radius,
+                axis,
+            )),
+            // Add bottom face
+            scene.add_surface(Surface::new_annulus(
+                self.translation - axis * self.height / 2.,
+                self.inner_radius,
+                self.outer_radius,
+                -axis,
+            )),
+            // Add outer side face
+            scene.add_surface(Surface::new_cylinder(
+                self.translation,
+                self.outer_radius,
+                self.height,
+                axis,
+            )),
+            // Add inner side face, facing the bore
+            scene.add_surface(
+                Surface::new_cylinder(self.translation, self.inner_radius, self.height, axis)
+                    .flip_normals(),
+            ),
+        ]
+    }
+}
+
+impl Primitive for Cone {
+    fn add_surfaces(&self, scene: &mut Scene) -> Vec<SurfaceId> {
+        let axis = self.axis.normalize();
 
-        // Add side face
-        scene.add_surface(Surface::new_cone(
-            self.translation,
-            self.radius,
-            self.height,
-            axis,
-        ));
+        vec![
+            // Add base face
+            scene.add_surface(Surface::new_disk(self.translation, self.radius, -axis)),
+            // Add side face
+            scene.add_surface(Surface::new_cone(
+                self.translation,
+                self.radius,
+                self.height,
+                axis,
+            )),
+        ]
     }
 }
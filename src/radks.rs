@@ -0,0 +1,59 @@
+use super::ViewFactors;
+
+/// How distribution factors are turned into radiation conductances
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RadkOptions {
+    /// Couplings that carry less than this fraction of the emission of both of their surfaces are
+    /// dropped. The threshold is relative: a coupling is compared with threshold times the
+    /// emitting area e_i A_i of each surface, not with an absolute conductance. Both directions of
+    /// a pair are dropped together, and only when each is negligible to both surfaces. Clipped
+    /// couplings are set to zero rather than moved elsewhere, so the heat they carried is left out
+    /// of the network.
+    pub threshold: f32,
+    /// Replaces each pair of couplings with their average, so the network is exactly reciprocal.
+    /// This happens before the threshold is applied.
+    pub symmetrize: bool,
+}
+
+/// Radiation conductances RADK_ij = e_i A_i B_ij between every pair of surfaces or groups, from a
+/// full matrix of gray body distribution factors B_ij and the emissivity times area e_i A_i of each
+/// emitter. The net heat flow from i to j is sigma RADK_ij (T_i^4 - T_j^4) when RADK_ij = RADK_ji.
+pub fn radks(
+    distribution_factors: &[ViewFactors],
+    emitting_areas: &[f32],
+    options: RadkOptions,
+) -> Vec<Vec<f32>> {
+    let n = emitting_areas.len();
+    assert_eq!(distribution_factors.len(), n);
+    assert!(distribution_factors
+        .iter()
+        .all(|row| row.estimates.len() == n));
+
+    let mut radks: Vec<Vec<f32>> = distribution_factors
+        .iter()
+        .zip(emitting_areas)
+        .map(|(row, area)| row.means().iter().map(|b_ij| area * b_ij).collect())
+        .collect();
+
+    if options.symmetrize {
+        let raw = radks;
+        radks = (0..n)
+            .map(|i| (0..n).map(|j| 0.5 * (raw[i][j] + raw[j][i])).collect())
+            .collect();
+    }
+
+    // A coupling is kept if it matters to either surface, and a symmetric pair is kept together
+    let negligible = |radk: f32, i: usize, j: usize| {
+        radk < options.threshold * emitting_areas[i] && radk < options.threshold * emitting_areas[j]
+    };
+    let kept =
+        |i: usize, j: usize| !(negligible(radks[i][j], i, j) && negligible(radks[j][i], i, j));
+
+    (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| if kept(i, j) { radks[i][j] } else { 0.0 })
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,174 @@
+use super::{linear::solve_linear_system, ViewFactors};
+
+/// Algorithm used to make a view factor matrix satisfy closure once reciprocity is enforced
+#[derive(Clone, Copy, Debug)]
+pub enum ReciprocityCorrection {
+    /// Smallest relative change to each exchange area A_i F_ij that satisfies the constraints
+    LeastSquares,
+    /// Rescales rows and matching columns until every row sum is within tolerance of one
+    IterativeScaling {
+        tolerance: f32,
+        max_iterations: usize,
+    },
+}
+
+/// How far a view factor matrix is from satisfying reciprocity and closure
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Imbalance {
+    /// Largest difference between A_i F_ij and A_j F_ji, relative to the larger of the two
+    pub max_reciprocity_error: f32,
+    /// Largest difference between a row sum and one
+    pub max_closure_error: f32,
+}
+
+impl Imbalance {
+    pub fn of(view_factors: &[Vec<f32>], areas: &[f32]) -> Self {
+        check_dimensions(view_factors, areas);
+
+        let mut max_reciprocity_error = 0.0f32;
+        let mut max_closure_error = 0.0f32;
+
+        for (i, row) in view_factors.iter().enumerate() {
+            for j in 0..i {
+                let g_ij = areas[i] * row[j];
+                let g_ji = areas[j] * view_factors[j][i];
+                let largest = g_ij.abs().max(g_ji.abs());
+                if largest > 0.0 {
+                    max_reciprocity_error =
+                        max_reciprocity_error.max((g_ij - g_ji).abs() / largest);
+                }
+            }
+
+            max_closure_error = max_closure_error.max((row.iter().sum::<f32>() - 1.0).abs());
+        }
+
+        Imbalance {
+            max_reciprocity_error,
+            max_closure_error,
+        }
+    }
+}
+
+/// View factor means adjusted to satisfy reciprocity, and closure for enclosures
+#[derive(Clone, Debug)]
+pub struct CorrectedViewFactors {
+    pub view_factors: Vec<Vec<f32>>,
+    /// Imbalance of the raw Monte Carlo estimates
+    pub imbalance: Imbalance,
+}
+
+/// Enforces A_i F_ij = A_j F_ji on a full view factor matrix, and also that every row sums to one
+/// when the surfaces form an enclosure
+pub fn correct_view_factors(
+    matrix: &[ViewFactors],
+    areas: &[f32],
+    enclosure: bool,
+    correction: ReciprocityCorrection,
+) -> CorrectedViewFactors {
+    let raw: Vec<Vec<f32>> = matrix.iter().map(|row| row.means()).collect();
+    let imbalance = Imbalance::of(&raw, areas);
+
+    let areas: Vec<f64> = areas.iter().map(|&area| area as f64).collect();
+    assert!(areas.iter().all(|&area| area > 0.0));
+
+    // Work with exchange areas G_ij = A_i F_ij, which reciprocity makes symmetric
+    let n = areas.len();
+    let mut g = vec![vec![0.0f64; n]; n];
+    for i in 0..n {
+        for j in 0..n {
+            let g_ij = areas[i] * raw[i][j] as f64;
+            let g_ji = areas[j] * raw[j][i] as f64;
+            g[i][j] = 0.5 * (g_ij + g_ji);
+        }
+    }
+
+    if enclosure {
+        match correction {
+            ReciprocityCorrection::LeastSquares => least_squares_closure(&mut g, &areas),
+            ReciprocityCorrection::IterativeScaling {
+                tolerance,
+                max_iterations,
+            } => iterative_scaling_closure(&mut g, &areas, tolerance as f64, max_iterations),
+        }
+    }
+
+    let view_factors = g
+        .iter()
+        .zip(&areas)
+        .map(|(row, area)| row.iter().map(|g_ij| (g_ij / area) as f32).collect())
+        .collect();
+
+    CorrectedViewFactors {
+        view_factors,
+        imbalance,
+    }
+}
+
+fn check_dimensions(view_factors: &[Vec<f32>], areas: &[f32]) {
+    assert_eq!(view_factors.len(), areas.len());
+    assert!(view_factors.iter().all(|row| row.len() == areas.len()));
+}
+
+// Minimizes sum((g_ij - g0_ij)^2 / g0_ij^2) over the symmetric g subject to row sums equal to
+// areas. Weighting by the square of each exchange area keeps zero entries at zero and spreads the
+// correction in proportion to the size of each entry. The Lagrange multipliers of the row
+// constraints solve a linear system the size of the matrix.
+fn least_squares_closure(g: &mut [Vec<f64>], areas: &[f64]) {
+    let n = areas.len();
+
+    let mut system = vec![vec![0.0; n + 1]; n];
+    for i in 0..n {
+        for j in 0..n {
+            let s = g[i][j] * g[i][j];
+            system[i][j] += s;
+            system[i][i] += s;
+        }
+        system[i][n] = g[i].iter().sum::<f64>() - areas[i];
+    }
+
+    // Rows without a usable pivot, such as surfaces that see nothing, get a zero multiplier
+    let multipliers = solve_linear_system(system).values;
+
+    for i in 0..n {
+        for j in 0..n {
+            let lambda = multipliers[i] + multipliers[j];
+            // Clamp in case a very large correction would make an entry negative
+            g[i][j] = (g[i][j] * (1.0 - g[i][j] * lambda)).max(0.0);
+        }
+    }
+}
+
+// Symmetric Sinkhorn scaling, g_ij <- d_i g_ij d_j, which keeps reciprocity at every step
+fn iterative_scaling_closure(
+    g: &mut [Vec<f64>],
+    areas: &[f64],
+    tolerance: f64,
+    max_iterations: usize,
+) {
+    let n = areas.len();
+
+    for _ in 0..max_iterations {
+        let row_sums: Vec<f64> = g.iter().map(|row| row.iter().sum()).collect();
+
+        let converged = row_sums
+            .iter()
+            .zip(areas)
+            .all(|(sum, area)| *sum == 0.0 || (sum / area - 1.0).abs() <= tolerance);
+        if converged {
+            break;
+        }
+
+        // Rows that see nothing cannot be scaled to close
+        let scales: Vec<f64> = row_sums
+            .iter()
+            .zip(areas)
+            .map(|(&sum, area)| if sum > 0.0 { (area / sum).sqrt() } else { 1.0 })
+            .collect();
+
+        for i in 0..n {
+            for j in 0..n {
+                g[i][j] *= scales[i] * scales[j];
+            }
+        }
+    }
+}
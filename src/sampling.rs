@@ -0,0 +1,281 @@
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Source of the random numbers used to sample ray origins and directions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sampling {
+    /// Independent pseudo-random draws
+    #[default]
+    PseudoRandom,
+    /// Low discrepancy Sobol points, one point per ray
+    /// With scrambling, rays alternate between independently scrambled sequences and standard
+    /// errors come from the spread between them. Without scrambling the points are deterministic,
+    /// so reported standard errors are not meaningful and adaptive ray counts are not supported.
+    Sobol { owen_scrambling: bool },
+}
+
+impl Sampling {
+    // Number of independently randomized sequences that rays are split between
+    pub(crate) fn num_replicates(&self) -> usize {
+        match self {
+            Sampling::Sobol {
+                owen_scrambling: true,
+            } => SOBOL_REPLICATES,
+            _ => 1,
+        }
+    }
+
+    // Whether standard errors estimate the actual error of the view factors
+    pub(crate) fn has_error_estimates(&self) -> bool {
+        !matches!(
+            self,
+            Sampling::Sobol {
+                owen_scrambling: false
+            }
+        )
+    }
+}
+
+// Scrambled Sobol sequences per emitter, which is enough for a usable estimate of their spread
+// while keeping each sequence long
+const SOBOL_REPLICATES: usize = 8;
+
+// Batch numbers stay below this bit, which tags the stream the scramble seeds are drawn from
+const SCRAMBLE_STREAM_TAG: u64 = 1 << 31;
+
+// Dimensions of each ray's Sobol point are allocated to the parts of its path
+pub(crate) const POSITION_DIMENSION: usize = 0;
+pub(crate) const DIRECTION_DIMENSION: usize = 2;
+pub(crate) const SIDE_DIMENSION: usize = 4;
+pub(crate) const MEMBER_DIMENSION: usize = 5;
+pub(crate) const BOUNCE_DIMENSION: usize = 6;
+
+/// Random number source for a batch of rays
+pub(crate) enum RaySampler {
+    PseudoRandom(ChaCha8Rng),
+    Sobol(SobolSampler),
+}
+
+impl RaySampler {
+    pub fn new(sampling: Sampling, seed: u64, stream: usize, batch: usize) -> Self {
+        assert!(
+            (batch as u64) < SCRAMBLE_STREAM_TAG,
+            "too many batches of rays"
+        );
+
+        // Each batch of rays from each surface or group gets its own deterministic random stream
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(((stream as u64) << 32) | batch as u64);
+
+        match sampling {
+            Sampling::PseudoRandom => RaySampler::PseudoRandom(rng),
+            Sampling::Sobol { owen_scrambling } => {
+                // Every batch from a surface must share scrambling to form the same sequences, so
+                // the seeds come from a stream of their own
+                let scramble_seeds = owen_scrambling.then(|| {
+                    let mut stream_rng = ChaCha8Rng::seed_from_u64(seed);
+                    stream_rng.set_stream(((stream as u64) << 32) | SCRAMBLE_STREAM_TAG);
+                    [(); SOBOL_REPLICATES].map(|_| stream_rng.next_u32())
+                });
+
+                RaySampler::Sobol(SobolSampler {
+                    index: 0,
+                    replicate: 0,
+                    dimension: 0,
+                    scramble_seeds,
+                    padding: rng,
+                })
+            }
+        }
+    }
+
+    // Moves to the point for a ray, indexed from the first ray traced from the surface. Scrambled
+    // rays take turns between the replicate sequences.
+    pub fn start_ray(&mut self, index: usize) {
+        if let RaySampler::Sobol(sobol) = self {
+            let num_replicates = if sobol.scramble_seeds.is_some() {
+                SOBOL_REPLICATES
+            } else {
+                1
+            };
+            sobol.index = (index / num_replicates) as u32;
+            sobol.replicate = index % num_replicates;
+            sobol.dimension = 0;
+        }
+    }
+
+    pub fn start_dimension(&mut self, dimension: usize) {
+        if let RaySampler::Sobol(sobol) = self {
+            sobol.dimension = dimension;
+        }
+    }
+}
+
+impl RngCore for RaySampler {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            RaySampler::PseudoRandom(rng) => rng.next_u32(),
+            RaySampler::Sobol(sobol) => sobol.next_u32(),
+        }
+    }
+
+    // The Sobol value fills the high bits, which are the ones used to make floats
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            RaySampler::PseudoRandom(rng) => rng.next_u64(),
+            RaySampler::Sobol(sobol) => {
+                let high = sobol.next_u32() as u64;
+                (high << 32) | sobol.padding.next_u32() as u64
+            }
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            RaySampler::PseudoRandom(rng) => rng.fill_bytes(dest),
+            RaySampler::Sobol(sobol) => sobol.padding.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Sobol sequence sampler that hands out successive dimensions of the current point
+pub(crate) struct SobolSampler {
+    index: u32,
+    replicate: usize,
+    dimension: usize,
+    // Independent scrambling for each replicate sequence
+    scramble_seeds: Option<[u32; SOBOL_REPLICATES]>,
+    // Dimensions past the table are padded with pseudo-random numbers
+    padding: ChaCha8Rng,
+}
+
+impl SobolSampler {
+    fn next_u32(&mut self) -> u32 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+
+        if dimension >= NUM_SOBOL_DIMENSIONS {
+            return self.padding.next_u32();
+        }
+
+        let value = sobol(self.index, dimension);
+
+        match self.scramble_seeds {
+            Some(seeds) => nested_uniform_scramble(
+                value,
+                hash_combine(seeds[self.replicate], dimension as u32),
+            ),
+            None => value,
+        }
+    }
+}
+
+// Sobol point for index in the given dimension, as a fraction of 2^32
+fn sobol(index: u32, dimension: usize) -> u32 {
+    let directions = &SOBOL_DIRECTIONS[dimension];
+
+    let mut value = 0;
+    let mut bits = index;
+    let mut bit = 0;
+    while bits != 0 {
+        if bits & 1 == 1 {
+            value ^= directions[bit];
+        }
+        bits >>= 1;
+        bit += 1;
+    }
+    value
+}
+
+// Hash based Owen scrambling
+// Source: Brent Burley, "Practical Hash-based Owen Scrambling", JCGT 2020
+fn nested_uniform_scramble(value: u32, seed: u32) -> u32 {
+    laine_karras_permutation(value.reverse_bits(), seed).reverse_bits()
+}
+
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x
+}
+
+fn hash_combine(seed: u32, value: u32) -> u32 {
+    seed ^ (value
+        .wrapping_add(0x9e3779b9)
+        .wrapping_add(seed << 6)
+        .wrapping_add(seed >> 2))
+}
+
+// Position, direction, emitting side, and group member, then two dimensions for each of the first
+// diffuse bounces
+const NUM_SOBOL_DIMENSIONS: usize = 16;
+
+// Degree, polynomial coefficients, and initial direction numbers for dimensions after the first
+// Source: S. Joe and F. Y. Kuo, new-joe-kuo-6.21201 direction numbers
+const SOBOL_PARAMETERS: [(u32, u32, [u32; 6]); NUM_SOBOL_DIMENSIONS - 1] = [
+    (1, 0, [1, 0, 0, 0, 0, 0]),
+    (2, 1, [1, 3, 0, 0, 0, 0]),
+    (3, 1, [1, 3, 1, 0, 0, 0]),
+    (3, 2, [1, 1, 1, 0, 0, 0]),
+    (4, 1, [1, 1, 3, 3, 0, 0]),
+    (4, 4, [1, 3, 5, 13, 0, 0]),
+    (5, 2, [1, 1, 5, 5, 17, 0]),
+    (5, 4, [1, 1, 5, 5, 5, 0]),
+    (5, 7, [1, 1, 7, 11, 19, 0]),
+    (5, 11, [1, 1, 5, 1, 1, 0]),
+    (5, 13, [1, 1, 1, 3, 11, 0]),
+    (5, 14, [1, 3, 5, 5, 31, 0]),
+    (6, 1, [1, 3, 3, 9, 7, 49]),
+    (6, 13, [1, 1, 1, 15, 21, 21]),
+    (6, 16, [1, 3, 1, 13, 27, 49]),
+];
+
+const SOBOL_DIRECTIONS: [[u32; 32]; NUM_SOBOL_DIMENSIONS] = sobol_directions();
+
+const fn sobol_directions() -> [[u32; 32]; NUM_SOBOL_DIMENSIONS] {
+    let mut directions = [[0u32; 32]; NUM_SOBOL_DIMENSIONS];
+
+    // First dimension is the van der Corput sequence
+    let mut bit = 0;
+    while bit < 32 {
+        directions[0][bit] = 1 << (31 - bit);
+        bit += 1;
+    }
+
+    let mut dimension = 1;
+    while dimension < NUM_SOBOL_DIMENSIONS {
+        let (degree, coefficients, initial) = SOBOL_PARAMETERS[dimension - 1];
+        let degree = degree as usize;
+        let v = &mut directions[dimension];
+
+        let mut bit = 0;
+        while bit < 32 {
+            v[bit] = if bit < degree {
+                initial[bit] << (31 - bit)
+            } else {
+                let mut value = v[bit - degree] ^ (v[bit - degree] >> degree);
+                let mut k = 1;
+                while k < degree {
+                    if (coefficients >> (degree - 1 - k)) & 1 == 1 {
+                        value ^= v[bit - k];
+                    }
+                    k += 1;
+                }
+                value
+            };
+            bit += 1;
+        }
+
+        dimension += 1;
+    }
+
+    directions
+}
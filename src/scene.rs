@@ -1,32 +1,325 @@
+use std::sync::OnceLock;
+
 use kiss3d::{light::Light, window::Window};
+use rand::{thread_rng, Rng};
 use rayon::prelude::*;
 
-use super::{primitives::Primitive, TraceRecord};
+use super::{
+    bvh::Bvh,
+    groups::{GroupId, SurfaceGroup, SurfaceId},
+    primitives::Primitive,
+    radks::{radks, RadkOptions},
+    sampling::{
+        RaySampler, Sampling, BOUNCE_DIMENSION, DIRECTION_DIMENSION, MEMBER_DIMENSION,
+        POSITION_DIMENSION, SIDE_DIMENSION,
+    },
+    view_factors::{ConvergenceTarget, Tally, ViewFactors},
+    TraceRecord,
+};
 
 use super::{
-    surfaces::{Material, Surface},
-    HitRecord, Ray,
+    surfaces::{DirectionalEmissivity, Material, Side, Surface},
+    HitRecord, Normal, Ray, SurfaceSample,
 };
 
+// Where the rays of a view factor computation come from, and what their energy is counted on
+struct Emitter {
+    source: Source,
+    // Slot that energy absorbed by each surface is counted in, if it is counted at all
+    slots: Vec<Option<usize>>,
+    num_targets: usize,
+    // First slot of the bins of each binned surface
+    bin_slots: Vec<Option<usize>>,
+    bin_counts: Vec<usize>,
+    // Independently randomized sequences that the rays are split between
+    num_replicates: usize,
+}
+
+enum Source {
+    Surface(usize),
+    Group {
+        group: usize,
+        // Member surfaces and the running total of their areas, for picking members by area
+        surfaces: Vec<usize>,
+        cumulative_areas: Vec<f32>,
+    },
+}
+
+impl Emitter {
+    // Random stream for the emitter's rays, with groups kept apart from surfaces by the top bit
+    fn stream(&self) -> usize {
+        match self.source {
+            Source::Surface(surface) => surface,
+            Source::Group { group, .. } => (1 << 31) | group,
+        }
+    }
+
+    fn new_tally(&self) -> Tally {
+        Tally::new(self.num_targets, &self.bin_counts, self.num_replicates)
+    }
+
+    // Slots that each absorption along a ray's path is counted in, along with the energy absorbed
+    // in a spectral band
+    fn deposits<'a>(
+        &'a self,
+        surfaces: &'a [Surface],
+        record: &'a TraceRecord,
+        band: usize,
+    ) -> impl Iterator<Item = (usize, f32)> + 'a {
+        record
+            .entries
+            .iter()
+            .enumerate()
+            .flat_map(move |(i, entry)| {
+                let surface = entry.surface_id;
+                let bin = self.bin_slots[surface]
+                    .map(|first_bin| first_bin + surfaces[surface].bin(&entry.point));
+                let energy_absorbed = record.band_energy_absorbed(i, band);
+
+                self.slots[surface]
+                    .into_iter()
+                    .chain(bin)
+                    .map(move |slot| (slot, energy_absorbed))
+            })
+    }
+}
+
 #[derive(Default)]
 pub struct Scene {
-    pub surfaces: Vec<Surface>,
+    // Only changed through the scene, so the BVH is always dropped when they change
+    surfaces: Vec<Surface>,
+    // Optional name for each surface, indexed like the surfaces
+    names: Vec<Option<String>>,
+    groups: Vec<SurfaceGroup>,
+    // Group of each surface, indexed like the surfaces
+    surface_groups: Vec<Option<GroupId>>,
+    // Names of the spectral bands that surfaces can have their own emissivity in
+    bands: Vec<String>,
+    // Built lazily on the first ray cast after the surfaces change
+    bvh: OnceLock<Bvh>,
+    // Fixed seed for reproducible runs, otherwise each computation draws a fresh one
+    seed: Option<u64>,
+    sampling: Sampling,
 }
 
 impl Scene {
-    // Cast a ray until it hits a surfacem then return the hit
+    pub fn surfaces(&self) -> &[Surface] {
+        &self.surfaces
+    }
+
+    pub fn surface(&self, surface: SurfaceId) -> &Surface {
+        &self.surfaces[surface.0]
+    }
+
+    // Changes to the surface are picked up by the next ray cast
+    pub fn surface_mut(&mut self, surface: SurfaceId) -> &mut Surface {
+        self.bvh.take();
+        &mut self.surfaces[surface.0]
+    }
+
+    pub fn areas(&self) -> Vec<f32> {
+        self.surfaces.iter().map(Surface::area).collect()
+    }
+
+    // Emissivity times area of each surface, summed over its active sides
+    pub fn emitting_areas(&self) -> Vec<f32> {
+        self.surfaces
+            .iter()
+            .map(|surface| surface.emitting_area(None))
+            .collect()
+    }
+
+    // Emissivity times area of each surface in a spectral band
+    pub fn band_emitting_areas(&self, band: usize) -> Vec<f32> {
+        self.surfaces
+            .iter()
+            .map(|surface| surface.emitting_area(Some(band)))
+            .collect()
+    }
+
+    // Radiation conductances between every pair of surfaces, from their distribution factor matrix
+    pub fn radks(&self, matrix: &[ViewFactors], options: RadkOptions) -> Vec<Vec<f32>> {
+        radks(matrix, &self.emitting_areas(), options)
+    }
+
+    // Radiation conductances between groups, summed over the surfaces in each from a surface RADK
+    // matrix. Couplings within a group are kept on the diagonal.
+    pub fn group_radks(&self, radks: &[Vec<f32>]) -> Vec<Vec<f32>> {
+        assert_eq!(radks.len(), self.surfaces.len());
+
+        self.groups
+            .iter()
+            .map(|group| {
+                self.groups
+                    .iter()
+                    .map(|other| {
+                        group
+                            .surfaces
+                            .iter()
+                            .flat_map(|i| other.surfaces.iter().map(|j| radks[i.0][j.0]))
+                            .sum()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Emissivity times area of each group, summed over its surfaces
+    pub fn group_emitting_areas(&self) -> Vec<f32> {
+        let emitting_areas = self.emitting_areas();
+        self.groups
+            .iter()
+            .map(|group| group.surfaces.iter().map(|s| emitting_areas[s.0]).sum())
+            .collect()
+    }
+
+    pub fn surface_name(&self, surface: SurfaceId) -> Option<&str> {
+        self.names.get(surface.0)?.as_deref()
+    }
+
+    pub fn find_surface(&self, name: &str) -> Option<SurfaceId> {
+        self.names
+            .iter()
+            .position(|surface_name| surface_name.as_deref() == Some(name))
+            .map(SurfaceId)
+    }
+
+    // Names must be unique so they can be used to look surfaces up
+    pub fn set_surface_name(&mut self, surface: SurfaceId, name: &str) {
+        assert!(
+            self.find_surface(name).is_none_or(|other| other == surface),
+            "a surface named {name} already exists"
+        );
+        assert!(surface.0 < self.surfaces.len());
+        self.names[surface.0] = Some(name.to_string());
+    }
+
+    pub fn groups(&self) -> &[SurfaceGroup] {
+        &self.groups
+    }
+
+    pub fn group(&self, group: GroupId) -> &SurfaceGroup {
+        &self.groups[group.0]
+    }
+
+    pub fn find_group(&self, name: &str) -> Option<GroupId> {
+        self.groups
+            .iter()
+            .position(|group| group.name == name)
+            .map(GroupId)
+    }
+
+    // Group containing the surface, if any
+    pub fn group_of(&self, surface: SurfaceId) -> Option<GroupId> {
+        self.surface_groups.get(surface.0).copied().flatten()
+    }
+
+    // Each surface can belong to at most one group, since a group stands for one thermal node.
+    // Groups must have some area to emit from.
+    pub fn add_group(&mut self, name: &str, surfaces: &[SurfaceId]) -> GroupId {
+        assert!(!surfaces.is_empty(), "group {name} has no surfaces");
+        assert!(
+            self.find_group(name).is_none(),
+            "a group named {name} already exists"
+        );
+        let group = GroupId(self.groups.len());
+
+        for &surface in surfaces {
+            assert!(surface.0 < self.surfaces.len());
+            assert!(
+                self.group_of(surface).is_none(),
+                "surface {} is already in a group",
+                surface.0
+            );
+            self.surface_groups[surface.0] = Some(group);
+        }
+
+        // Groups emit from their members in proportion to area, so they need some
+        let area: f32 = surfaces
+            .iter()
+            .map(|surface| self.surfaces[surface.0].area())
+            .sum();
+        assert!(area > 0.0, "group {name} has no area");
+
+        self.groups.push(SurfaceGroup {
+            name: name.to_string(),
+            surfaces: surfaces.to_vec(),
+        });
+        group
+    }
+
+    // Names the spectral bands that surfaces can have their own emissivity in, e.g. solar and
+    // infrared. Band computations return view factors for each band, indexed like the names.
+    pub fn set_bands(&mut self, names: &[&str]) {
+        for (i, name) in names.iter().enumerate() {
+            assert!(
+                !names[..i].contains(name),
+                "a band named {name} already exists"
+            );
+        }
+        self.bands = names.iter().map(|name| name.to_string()).collect();
+    }
+
+    pub fn bands(&self) -> &[String] {
+        &self.bands
+    }
+
+    pub fn find_band(&self, name: &str) -> Option<usize> {
+        self.bands.iter().position(|band| band == name)
+    }
+
+    // Number of bands for a band computation, checking every surface's emissivities fit in them
+    fn num_bands(&self) -> usize {
+        assert!(!self.bands.is_empty(), "the scene has no spectral bands");
+        for (i, surface) in self.surfaces.iter().enumerate() {
+            assert!(
+                surface.num_band_emissivities() <= self.bands.len(),
+                "surface {i} has an emissivity for band {} but the scene has {} bands",
+                surface.num_band_emissivities() - 1,
+                self.bands.len()
+            );
+        }
+        self.bands.len()
+    }
+
+    // Makes every view factor computation reproducible, or random again when set to None
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    pub fn set_sampling(&mut self, sampling: Sampling) {
+        self.sampling = sampling;
+    }
+
+    fn run_seed(&self) -> u64 {
+        self.seed.unwrap_or_else(|| thread_rng().gen())
+    }
+
+    // Each batch of rays from each surface gets its own deterministic random stream, so
+    // results only depend on the seed and not on how batches are spread over threads
+    fn batch_sampler(&self, seed: u64, stream: usize, batch: usize) -> RaySampler {
+        RaySampler::new(self.sampling, seed, stream, batch)
+    }
+
+    // Cast a ray until it hits a surface then return the hit
     pub fn cast_ray(&self, ray: &Ray) -> Option<HitRecord> {
+        let bvh = self.bvh.get_or_init(|| Bvh::build(&self.surfaces));
+        bvh.cast_ray(&self.surfaces, ray)
+    }
+
+    // Reference implementation for cast_ray that checks every surface
+    pub fn cast_ray_brute_force(&self, ray: &Ray) -> Option<HitRecord> {
         let mut closest_hit_opt = None;
 
         // Brute force -> run through all the objects naively
         for (surface_id, object) in self.surfaces.iter().enumerate() {
             if let Some(hit) = object.intersect(ray) {
                 if closest_hit_opt.is_none() {
-                    closest_hit_opt = Some(HitRecord { surface_id, hit });
+                    closest_hit_opt = Some(HitRecord::new(surface_id, hit, ray));
                 } else if let Some(closest_hit) = &mut closest_hit_opt {
                     // Check if this is closer than previous hit
                     if hit.t < closest_hit.hit.t {
-                        *closest_hit = HitRecord { surface_id, hit };
+                        *closest_hit = HitRecord::new(surface_id, hit, ray);
                     }
                 }
             }
@@ -38,8 +331,22 @@ impl Scene {
     const MIN_ENERGY: f32 = 1e-3;
 
     // Trace ray through reflections etc.
-    pub fn trace_ray(&self, mut ray: Ray) -> TraceRecord {
-        let mut energy = 1.0;
+    pub fn trace_ray(&self, ray: Ray, rng: &mut impl Rng) -> TraceRecord {
+        self.trace(ray, vec![1.0], false, rng)
+    }
+
+    // Traces a ray carrying energy in each spectral band, or in the surfaces' own optics if it is
+    // not in bands. Every band follows the same path, so choices that depend on the band are made
+    // with the average chance over the bands and each band's energy is weighted by its own chance.
+    fn trace(
+        &self,
+        mut ray: Ray,
+        mut energies: Vec<f32>,
+        in_bands: bool,
+        rng: &mut impl Rng,
+    ) -> TraceRecord {
+        let mut absorptivities = vec![0.0; energies.len()];
+        let mut energies_absorbed = vec![0.0; energies.len()];
         let mut reflections = 0;
 
         let mut record = TraceRecord::new(ray.clone());
@@ -47,30 +354,106 @@ impl Scene {
         while let Some(hit_record) = self.cast_ray(&ray) {
             let surface = &self.surfaces[hit_record.surface_id];
 
-            let energy_absorbed = energy * surface.emissivity;
+            // Inactive sides block rays without taking part in the exchange
+            if !surface.active_sides.contains(hit_record.side) {
+                energies_absorbed.fill(0.0);
+                record.add_entry(ray.clone(), hit_record, &energies_absorbed);
+                record.terminate_early();
+                break;
+            }
+
+            let optics = surface.optics(hit_record.side);
+            let normal = hit_record.facing_normal();
+            let cos_theta = -ray.direction.normalize().dot(normal.0);
+
+            for (band, absorptivity) in absorptivities.iter_mut().enumerate() {
+                *absorptivity = if in_bands {
+                    surface.band_absorptivity(hit_record.side, band, cos_theta)
+                } else {
+                    optics.absorptivity(cos_theta)
+                };
+            }
 
             // Update loop variables
             reflections += 1;
-            energy -= energy_absorbed;
-
-            // Calculate relfected ray direction
-            let reflected_direction = match surface.material {
-                Material::Diffuse => hit_record.hit.normal.to_diffuse_ray(),
-                Material::Specular => {
-                    ray.direction
-                        - 2. * ray.direction.dot(hit_record.hit.normal.0) * hit_record.hit.normal.0
+            for ((energy, energy_absorbed), absorptivity) in energies
+                .iter_mut()
+                .zip(&mut energies_absorbed)
+                .zip(&absorptivities)
+            {
+                *energy_absorbed = *energy * absorptivity;
+                *energy -= *energy_absorbed;
+            }
+
+            // Calculate relfected or transmitted ray direction
+            let direction = match &optics.material {
+                Material::Diffuse => normal.to_diffuse_ray(rng),
+                Material::Specular => normal.to_specular_ray(ray.direction),
+                Material::Mixed { specularity } => {
+                    if rng.gen_range(0.0..1.0) < *specularity {
+                        normal.to_specular_ray(ray.direction)
+                    } else {
+                        normal.to_diffuse_ray(rng)
+                    }
+                }
+                Material::Transmissive {
+                    transmissivity,
+                    refractive_index,
+                } => {
+                    // Share of the energy left after absorption that passes through the surface
+                    let transmitted = |absorptivity: f32| {
+                        if absorptivity < 1. {
+                            transmissivity / (1. - absorptivity)
+                        } else {
+                            0.
+                        }
+                    };
+                    let chance = absorptivities
+                        .iter()
+                        .map(|&absorptivity| transmitted(absorptivity))
+                        .sum::<f32>()
+                        / absorptivities.len() as f32;
+
+                    let transmits = rng.gen_range(0.0..1.0) < chance;
+                    if energies.len() > 1 {
+                        for (energy, &absorptivity) in energies.iter_mut().zip(&absorptivities) {
+                            *energy *= if transmits {
+                                transmitted(absorptivity) / chance
+                            } else {
+                                (1. - transmitted(absorptivity)) / (1. - chance)
+                            };
+                        }
+                    }
+
+                    if transmits {
+                        // Rays leaving through the back pass from the medium behind to the front
+                        let relative_index = match (*refractive_index, hit_record.side) {
+                            (None, _) => 1.0,
+                            (Some(index), Side::Front) => 1.0 / index,
+                            (Some(index), Side::Back) => index,
+                        };
+
+                        // Total internal reflection sends the ray back like a mirror
+                        normal
+                            .to_refracted_ray(ray.direction, relative_index)
+                            .unwrap_or_else(|| normal.to_specular_ray(ray.direction))
+                    } else {
+                        normal.to_specular_ray(ray.direction)
+                    }
                 }
+                Material::Brdf(brdf) => brdf.sample_direction(&normal, ray.direction, rng),
             };
 
             // Create reflected ray
             ray = Ray {
                 origin: hit_record.hit.position.clone(),
-                direction: reflected_direction,
+                direction,
             };
 
             // Record reflection
-            record.add_entry(ray.clone(), hit_record, energy_absorbed);
+            record.add_entry(ray.clone(), hit_record, &energies_absorbed);
 
+            let energy = energies.iter().copied().fold(0.0, f32::max);
             if reflections > Scene::MAX_REFLECTIONS || energy < Scene::MIN_ENERGY {
                 record.terminate_early();
                 break;
@@ -80,80 +463,556 @@ impl Scene {
         record
     }
 
-    pub fn view_factors_for_surface(&self, surface: usize, num_rays: usize) -> Vec<f32> {
+    pub fn view_factors_for_surface(&self, surface: SurfaceId, num_rays: usize) -> ViewFactors {
+        assert!(num_rays > 0);
+        let emitter = self.surface_emitter(surface.0);
+        let seed = self.run_seed();
+
+        // Same batches as the parallel version, so a fixed seed gives identical results
+        Scene::ray_batches(num_rays, 0)
+            .map(|(batch, batch_size)| {
+                let mut sampler = self.batch_sampler(seed, emitter.stream(), batch);
+                self.tally_rays(&emitter, batch, batch_size, &mut sampler)
+            })
+            .fold(emitter.new_tally(), Tally::merge)
+            .view_factors()
+    }
+
+    fn surface_emitter(&self, surface: usize) -> Emitter {
         // Check the surface id is valid
         assert!(surface < self.surfaces.len());
 
-        // Make vec to hold view factors
-        let mut view_factors = vec![0.0f32; self.surfaces.len()];
+        let slots = (0..self.surfaces.len()).map(Some).collect();
+        self.emitter(Source::Surface(surface), slots, self.surfaces.len())
+    }
 
-        // Count energy that hits each surface
-        for _ in 0..num_rays {
-            // Sample from last surface added to scene
-            let sample = self.surfaces[surface].sample();
+    fn group_emitter(&self, group: GroupId) -> Emitter {
+        let surfaces: Vec<usize> = self.group(group).surfaces.iter().map(|s| s.0).collect();
 
-            let ray = Ray {
-                origin: sample.position,
-                direction: sample.normal.to_diffuse_ray(),
-            };
+        let mut total_area = 0.0;
+        let cumulative_areas = surfaces
+            .iter()
+            .map(|&surface| {
+                total_area += self.surfaces[surface].area();
+                total_area
+            })
+            .collect();
+
+        // Energy is counted on the group of each surface, and not at all outside of groups
+        let slots = (0..self.surfaces.len())
+            .map(|surface| self.group_of(SurfaceId(surface)).map(GroupId::index))
+            .collect();
+
+        let source = Source::Group {
+            group: group.0,
+            surfaces,
+            cumulative_areas,
+        };
+        self.emitter(source, slots, self.groups.len())
+    }
+
+    // Binned surfaces get slots for their bins after the targets
+    fn emitter(&self, source: Source, slots: Vec<Option<usize>>, num_targets: usize) -> Emitter {
+        let mut next_slot = num_targets;
+        let mut bin_slots = Vec::with_capacity(self.surfaces.len());
+        let mut bin_counts = Vec::with_capacity(self.surfaces.len());
+
+        for surface in &self.surfaces {
+            if surface.num_bins() > 1 {
+                bin_slots.push(Some(next_slot));
+                bin_counts.push(surface.num_bins());
+                next_slot += surface.num_bins();
+            } else {
+                bin_slots.push(None);
+                bin_counts.push(0);
+            }
+        }
+
+        Emitter {
+            source,
+            slots,
+            num_targets,
+            bin_slots,
+            bin_counts,
+            num_replicates: self.sampling.num_replicates(),
+        }
+    }
+
+    // Diffuse ray from a random point on an active side of the emitter. Quasi-random points are
+    // indexed from the first ray traced from the emitter.
+    fn emit_ray(&self, emitter: &Emitter, index: usize, sampler: &mut RaySampler) -> Ray {
+        let (surface, mut sample) = self.emission_sample(emitter, index, sampler);
+
+        sampler.start_dimension(SIDE_DIMENSION);
+        let side = surface.emission_side(sampler);
+        if side == Side::Back {
+            sample.normal = Normal::new(-sample.normal.0);
+        }
+
+        sampler.start_dimension(DIRECTION_DIMENSION);
+        let direction = surface
+            .optics(side)
+            .emission_direction(&sample.normal, sampler);
+        let ray = Ray {
+            origin: sample.position,
+            direction,
+        };
+
+        sampler.start_dimension(BOUNCE_DIMENSION);
+        ray
+    }
+
+    // Ray from the emitter along with the energy it carries in each spectral band. The side and
+    // direction are drawn from the average of the bands' emission, and each band is weighted by
+    // how likely its own emission is to have produced them.
+    fn emit_ray_in_bands(
+        &self,
+        emitter: &Emitter,
+        index: usize,
+        sampler: &mut RaySampler,
+        num_bands: usize,
+    ) -> (Ray, Vec<f32>) {
+        let (surface, mut sample) = self.emission_sample(emitter, index, sampler);
+
+        let front_fractions: Vec<f32> = (0..num_bands)
+            .map(|band| surface.front_emission_fraction(Some(band)))
+            .collect();
+        let front_fraction = front_fractions.iter().sum::<f32>() / num_bands as f32;
+
+        sampler.start_dimension(SIDE_DIMENSION);
+        let side = surface.pick_emission_side(front_fraction, sampler);
+        let mut energies: Vec<f32> = front_fractions
+            .iter()
+            .map(|&fraction| match side {
+                Side::Front => fraction / front_fraction,
+                Side::Back => (1. - fraction) / (1. - front_fraction),
+            })
+            .collect();
+        if side == Side::Back {
+            sample.normal = Normal::new(-sample.normal.0);
+        }
+
+        // Each band emits along its directional emissivity, or diffusely without one
+        sampler.start_dimension(DIRECTION_DIMENSION);
+        let directionals: Vec<Option<&DirectionalEmissivity>> = (0..num_bands)
+            .map(|band| surface.band_directional(side, band))
+            .collect();
+        let direction = match directionals[0] {
+            None if directionals.iter().all(Option::is_none) => {
+                sample.normal.to_diffuse_ray(sampler)
+            }
+            Some(first)
+                if directionals.iter().all(|directional| {
+                    directional.is_some_and(|directional| std::ptr::eq(directional, first))
+                }) =>
+            {
+                first.sample_direction(&sample.normal, sampler)
+            }
+            _ => {
+                let band = sampler.gen_range(0..num_bands);
+                let direction = match directionals[band] {
+                    Some(directional) => directional.sample_direction(&sample.normal, sampler),
+                    None => sample.normal.to_diffuse_ray(sampler),
+                };
 
-            let record = self.trace_ray(ray);
+                // Density of each band's emission relative to diffuse emission
+                let cos_theta = direction.normalize().dot(sample.normal.0);
+                let densities: Vec<f32> = directionals
+                    .iter()
+                    .map(|directional| match directional {
+                        Some(directional) if directional.hemispherical() > 0. => {
+                            directional.at(cos_theta) / directional.hemispherical()
+                        }
+                        _ => 1.,
+                    })
+                    .collect();
+                let density = densities.iter().sum::<f32>() / num_bands as f32;
+                for (energy, band_density) in energies.iter_mut().zip(densities) {
+                    *energy *= band_density / density;
+                }
+                direction
+            }
+        };
+        let ray = Ray {
+            origin: sample.position,
+            direction,
+        };
 
-            for entry in record.entries {
-                view_factors[entry.surface_id] += entry.energy_absorbed;
+        sampler.start_dimension(BOUNCE_DIMENSION);
+        (ray, energies)
+    }
+
+    // Random point on the emitter, picking a member of a group by area
+    fn emission_sample(
+        &self,
+        emitter: &Emitter,
+        index: usize,
+        sampler: &mut RaySampler,
+    ) -> (&Surface, SurfaceSample) {
+        sampler.start_ray(index);
+
+        let surface = match &emitter.source {
+            Source::Surface(surface) => &self.surfaces[*surface],
+            Source::Group {
+                surfaces,
+                cumulative_areas,
+                ..
+            } => {
+                // Members emit in proportion to their area
+                sampler.start_dimension(MEMBER_DIMENSION);
+                let total_area = cumulative_areas[cumulative_areas.len() - 1];
+                let u = sampler.gen_range(0.0..total_area);
+                let member = cumulative_areas
+                    .partition_point(|&area| area <= u)
+                    .min(surfaces.len() - 1);
+                &self.surfaces[surfaces[member]]
             }
+        };
+
+        sampler.start_dimension(POSITION_DIMENSION);
+        (surface, surface.sample(sampler))
+    }
+
+    // Count energy that each ray in a batch from the emitter deposits in every slot
+    fn tally_rays(
+        &self,
+        emitter: &Emitter,
+        batch: usize,
+        num_rays: usize,
+        sampler: &mut RaySampler,
+    ) -> Tally {
+        let mut tally = emitter.new_tally();
+
+        for i in 0..num_rays {
+            let index = batch * Scene::RAY_BATCH_SIZE + i;
+            let ray = self.emit_ray(emitter, index, sampler);
+            let record = self.trace_ray(ray, sampler);
+            tally.add_deposits(index, emitter.deposits(&self.surfaces, &record, 0));
         }
 
-        // Divide view factors by count
-        for view_factor in view_factors.iter_mut() {
-            *view_factor /= num_rays as f32;
+        tally
+    }
+
+    // Tallies for each spectral band of the energy that each ray in a batch deposits
+    fn tally_rays_in_bands(
+        &self,
+        emitter: &Emitter,
+        batch: usize,
+        num_rays: usize,
+        sampler: &mut RaySampler,
+        num_bands: usize,
+    ) -> Vec<Tally> {
+        let mut tallies = vec![emitter.new_tally(); num_bands];
+
+        for i in 0..num_rays {
+            let index = batch * Scene::RAY_BATCH_SIZE + i;
+            let (ray, energies) = self.emit_ray_in_bands(emitter, index, sampler, num_bands);
+            let record = self.trace(ray, energies, true, sampler);
+            for (band, tally) in tallies.iter_mut().enumerate() {
+                tally.add_deposits(index, emitter.deposits(&self.surfaces, &record, band));
+            }
         }
 
-        view_factors
+        tallies
+    }
+
+    fn tally_rays_in_bands_parallel(
+        &self,
+        emitter: &Emitter,
+        num_rays: usize,
+        seed: u64,
+        num_bands: usize,
+    ) -> Vec<Tally> {
+        let batches: Vec<Vec<Tally>> = Scene::ray_batches(num_rays, 0)
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .map(|(batch, batch_size)| {
+                let mut sampler = self.batch_sampler(seed, emitter.stream(), batch);
+                self.tally_rays_in_bands(emitter, batch, batch_size, &mut sampler, num_bands)
+            })
+            .collect();
+
+        // Merge in batch order, since floating point sums depend on the order
+        batches
+            .into_iter()
+            .fold(vec![emitter.new_tally(); num_bands], |tallies, batch| {
+                tallies
+                    .into_iter()
+                    .zip(batch)
+                    .map(|(tally, other)| tally.merge(other))
+                    .collect()
+            })
     }
 
-    pub fn view_factors_for_surface_parallel(&self, surface: usize, num_rays: usize) -> Vec<f32> {
-        let num_threads = rayon::current_num_threads();
+    // Rays are traced in batches of this size
+    const RAY_BATCH_SIZE: usize = 10_000;
+
+    // Index and size of each batch needed to trace num_rays, numbered from first_batch
+    fn ray_batches(num_rays: usize, first_batch: usize) -> impl Iterator<Item = (usize, usize)> {
+        (0..num_rays.div_ceil(Scene::RAY_BATCH_SIZE)).map(move |i| {
+            let batch_size = Scene::RAY_BATCH_SIZE.min(num_rays - i * Scene::RAY_BATCH_SIZE);
+            (first_batch + i, batch_size)
+        })
+    }
 
-        let rays_per_thread = num_rays / num_threads;
+    pub fn view_factors_for_surface_parallel(
+        &self,
+        surface: SurfaceId,
+        num_rays: usize,
+    ) -> ViewFactors {
+        assert!(num_rays > 0);
+        self.tally_rays_parallel(
+            &self.surface_emitter(surface.0),
+            num_rays,
+            self.run_seed(),
+            0,
+        )
+        .view_factors()
+    }
 
-        let mut view_factors = (0..num_threads)
+    fn tally_rays_parallel(
+        &self,
+        emitter: &Emitter,
+        num_rays: usize,
+        seed: u64,
+        first_batch: usize,
+    ) -> Tally {
+        let tallies: Vec<Tally> = Scene::ray_batches(num_rays, first_batch)
+            .collect::<Vec<_>>()
             .into_par_iter()
-            .map(|_| self.view_factors_for_surface(surface, rays_per_thread))
-            .reduce(
-                || vec![0.0f32; self.surfaces.len()],
-                |mut state, value| {
-                    for (new, old) in value.iter().zip(state.iter_mut()) {
-                        *old += new;
-                    }
-                    state
-                },
-            );
+            .map(|(batch, batch_size)| {
+                let mut sampler = self.batch_sampler(seed, emitter.stream(), batch);
+                self.tally_rays(emitter, batch, batch_size, &mut sampler)
+            })
+            .collect();
+
+        // Merge in batch order, since floating point sums depend on the order
+        tallies.into_iter().fold(emitter.new_tally(), Tally::merge)
+    }
 
-        // Divide view factors by thread_count
-        for view_factor in view_factors.iter_mut() {
-            *view_factor /= num_threads as f32;
+    pub fn view_factors_for_surface_adaptive(
+        &self,
+        surface: SurfaceId,
+        target: &ConvergenceTarget,
+    ) -> ViewFactors {
+        self.view_factors_adaptive_seeded(&self.surface_emitter(surface.0), target, self.run_seed())
+    }
+
+    // Keep tracing rounds of parallel batches until the target is met or the ray budget runs out
+    fn view_factors_adaptive_seeded(
+        &self,
+        emitter: &Emitter,
+        target: &ConvergenceTarget,
+        seed: u64,
+    ) -> ViewFactors {
+        assert!(target.max_rays > 0);
+        assert!(
+            self.sampling.has_error_estimates(),
+            "adaptive ray counts need scrambled Sobol points or pseudo-random sampling"
+        );
+
+        let mut tally = emitter.new_tally();
+        let mut rays_in_round = Scene::RAY_BATCH_SIZE;
+        let mut next_batch = 0;
+
+        loop {
+            let num_rays = rays_in_round.min(target.max_rays - tally.num_rays());
+            tally = tally.merge(self.tally_rays_parallel(emitter, num_rays, seed, next_batch));
+            next_batch += num_rays.div_ceil(Scene::RAY_BATCH_SIZE);
+
+            let view_factors = tally.view_factors();
+            if tally.num_rays() >= target.max_rays || target.is_met(&view_factors) {
+                return view_factors;
+            }
+
+            // Standard error shrinks with the square root of the ray count, so size the next
+            // round from how far the least converged view factor is from the target
+            let rays_needed = tally.num_rays() as f32 * target.rays_needed_factor(&view_factors);
+            rays_in_round = (rays_needed as usize)
+                .saturating_sub(tally.num_rays())
+                .max(Scene::RAY_BATCH_SIZE);
         }
+    }
+
+    // Row i holds the view factors from surface i to every surface in the scene
+    pub fn view_factor_matrix(&self, num_rays_per_surface: usize) -> Vec<ViewFactors> {
+        assert!(num_rays_per_surface > 0);
+        let seed = self.run_seed();
 
-        view_factors
+        // Rows run in parallel, and the batches of rays within each row do too
+        (0..self.surfaces.len())
+            .into_par_iter()
+            .map(|surface| {
+                self.tally_rays_parallel(
+                    &self.surface_emitter(surface),
+                    num_rays_per_surface,
+                    seed,
+                    0,
+                )
+                .view_factors()
+            })
+            .collect()
     }
 
-    pub fn debug_rays_from_surface(&self, surface: usize, num_rays: usize) {
-        // Check the surface id is valid
-        assert!(surface < self.surfaces.len());
+    // Each row is traced until it meets the target on its own
+    pub fn view_factor_matrix_adaptive(&self, target: &ConvergenceTarget) -> Vec<ViewFactors> {
+        let seed = self.run_seed();
 
-        let mut records = Vec::new();
+        (0..self.surfaces.len())
+            .into_par_iter()
+            .map(|surface| {
+                self.view_factors_adaptive_seeded(&self.surface_emitter(surface), target, seed)
+            })
+            .collect()
+    }
+
+    // View factors from a surface in each spectral band of the scene, using the emissivity each
+    // surface has in the band. All bands are traced along the same ray paths.
+    pub fn view_factors_for_surface_in_bands(
+        &self,
+        surface: SurfaceId,
+        num_rays: usize,
+    ) -> Vec<ViewFactors> {
+        assert!(num_rays > 0);
+        let num_bands = self.num_bands();
+        self.tally_rays_in_bands_parallel(
+            &self.surface_emitter(surface.0),
+            num_rays,
+            self.run_seed(),
+            num_bands,
+        )
+        .iter()
+        .map(Tally::view_factors)
+        .collect()
+    }
 
-        for _ in 0..num_rays {
-            // Sample from last surface added to scene
-            let sample = self.surfaces[surface].sample();
+    // Matrix i holds the view factor matrix in band i
+    pub fn view_factor_matrices_in_bands(
+        &self,
+        num_rays_per_surface: usize,
+    ) -> Vec<Vec<ViewFactors>> {
+        assert!(num_rays_per_surface > 0);
+        let num_bands = self.num_bands();
+        let seed = self.run_seed();
 
-            let ray = Ray {
-                origin: sample.position,
-                direction: sample.normal.to_diffuse_ray(),
-            };
+        let rows: Vec<Vec<ViewFactors>> = (0..self.surfaces.len())
+            .into_par_iter()
+            .map(|surface| {
+                self.tally_rays_in_bands_parallel(
+                    &self.surface_emitter(surface),
+                    num_rays_per_surface,
+                    seed,
+                    num_bands,
+                )
+                .iter()
+                .map(Tally::view_factors)
+                .collect()
+            })
+            .collect();
+
+        (0..num_bands)
+            .map(|band| rows.iter().map(|row| row[band].clone()).collect())
+            .collect()
+    }
+
+    // View factors from a group to every group, emitting rays from its surfaces in proportion to
+    // their area. Energy absorbed by surfaces outside of any group is not reported.
+    pub fn view_factors_for_group(&self, group: GroupId, num_rays: usize) -> ViewFactors {
+        assert!(num_rays > 0);
+        self.tally_rays_parallel(&self.group_emitter(group), num_rays, self.run_seed(), 0)
+            .view_factors()
+    }
+
+    pub fn view_factors_for_group_adaptive(
+        &self,
+        group: GroupId,
+        target: &ConvergenceTarget,
+    ) -> ViewFactors {
+        self.view_factors_adaptive_seeded(&self.group_emitter(group), target, self.run_seed())
+    }
 
-            let record = self.trace_ray(ray);
+    // Row I holds the view factors from group I to every group in the scene
+    pub fn view_factor_matrix_for_groups(&self, num_rays_per_group: usize) -> Vec<ViewFactors> {
+        assert!(num_rays_per_group > 0);
+        let seed = self.run_seed();
+
+        (0..self.groups.len())
+            .into_par_iter()
+            .map(|group| {
+                self.tally_rays_parallel(
+                    &self.group_emitter(GroupId(group)),
+                    num_rays_per_group,
+                    seed,
+                    0,
+                )
+                .view_factors()
+            })
+            .collect()
+    }
+
+    pub fn view_factor_matrix_for_groups_adaptive(
+        &self,
+        target: &ConvergenceTarget,
+    ) -> Vec<ViewFactors> {
+        let seed = self.run_seed();
+
+        (0..self.groups.len())
+            .into_par_iter()
+            .map(|group| {
+                self.view_factors_adaptive_seeded(&self.group_emitter(GroupId(group)), target, seed)
+            })
+            .collect()
+    }
+
+    // View factors from a surface to each group, summed over the surfaces in the group
+    pub fn view_factors_to_groups(&self, view_factors: &ViewFactors) -> Vec<f32> {
+        assert_eq!(view_factors.estimates.len(), self.surfaces.len());
+
+        self.groups
+            .iter()
+            .map(|group| {
+                group
+                    .surfaces
+                    .iter()
+                    .map(|surface| view_factors[surface.0].mean)
+                    .sum()
+            })
+            .collect()
+    }
+
+    // Row I holds the view factors from group I to every group, from a full view factor matrix.
+    // Rows of the emitting surfaces are weighted by area, since larger surfaces emit more.
+    pub fn group_view_factor_matrix(&self, matrix: &[ViewFactors]) -> Vec<Vec<f32>> {
+        assert_eq!(matrix.len(), self.surfaces.len());
+
+        self.groups
+            .iter()
+            .map(|group| {
+                let mut row = vec![0.0; self.groups.len()];
+                let mut group_area = 0.0;
+
+                for surface in &group.surfaces {
+                    let area = self.surfaces[surface.0].area();
+                    let to_groups = self.view_factors_to_groups(&matrix[surface.0]);
+                    for (value, view_factor) in row.iter_mut().zip(to_groups) {
+                        *value += area * view_factor;
+                    }
+                    group_area += area;
+                }
+
+                row.iter().map(|value| value / group_area).collect()
+            })
+            .collect()
+    }
+
+    pub fn debug_rays_from_surface(&self, surface: SurfaceId, num_rays: usize) {
+        let mut records = Vec::new();
+        let emitter = self.surface_emitter(surface.0);
+        let mut rng = self.batch_sampler(self.run_seed(), emitter.stream(), 0);
+
+        for i in 0..num_rays {
+            let ray = self.emit_ray(&emitter, i, &mut rng);
+            let record = self.trace_ray(ray, &mut rng);
 
             records.push(record);
         }
@@ -179,11 +1038,25 @@ impl Scene {
         }
     }
 
-    pub fn add_surface(&mut self, surface: Surface) {
+    pub fn add_surface(&mut self, surface: Surface) -> SurfaceId {
         self.surfaces.push(surface);
+        self.names.resize(self.surfaces.len(), None);
+        self.surface_groups.resize(self.surfaces.len(), None);
+
+        // Rebuild the BVH to include the new surface
+        self.bvh.take();
+
+        SurfaceId(self.surfaces.len() - 1)
+    }
+
+    pub fn add_named_surface(&mut self, name: &str, surface: Surface) -> SurfaceId {
+        let id = self.add_surface(surface);
+        self.set_surface_name(id, name);
+        id
     }
 
-    pub fn add_primitive(&mut self, primitive: impl Primitive) {
-        primitive.add_surfaces(self);
+    // Returns the surfaces the primitive added, in the order it added them
+    pub fn add_primitive(&mut self, primitive: impl Primitive) -> Vec<SurfaceId> {
+        primitive.add_surfaces(self)
     }
 }
@@ -0,0 +1,90 @@
+use std::ops::Range;
+
+use super::{in_sweep, sweep_fraction, sweep_range};
+use crate::{Hit, Normal, Point, Ray, SurfaceSample};
+
+use glam::{Mat3, Vec3};
+use rand::Rng;
+
+// Local annulus lies between inner_radius and 1 on the xy plane
+pub fn sample(
+    rng: &mut impl Rng,
+    inner_radius: f32,
+    theta_min: f32,
+    theta_max: f32,
+) -> SurfaceSample {
+    let theta = rng.gen_range(theta_min..theta_max);
+
+    // Uniform in r^2 between the inner and outer radius, so samples are uniform over the area
+    let r_squared = inner_radius * inner_radius;
+    let r = (r_squared + rng.gen_range(0.0..1.0) as f32 * (1. - r_squared)).sqrt();
+
+    let x = r * theta.cos();
+    let y = r * theta.sin();
+
+    SurfaceSample {
+        position: Point(Vec3 { x, y, z: 0.0 }),
+        normal: Normal::new(Vec3::Z),
+    }
+}
+
+pub fn intersect(ray: &Ray, inner_radius: f32, theta_min: f32, theta_max: f32) -> Option<Hit> {
+    // Ensure ray intersects plane
+    if ray.direction.z == 0.0 {
+        return None;
+    }
+
+    let t = -ray.origin.0.z / ray.direction.z;
+
+    if t < 0.0001 {
+        return None;
+    }
+
+    let pos = ray.at(t);
+    let r_squared = pos.dot(pos);
+
+    if r_squared < 1.
+        && r_squared >= inner_radius * inner_radius
+        && in_sweep(pos, theta_min, theta_max)
+    {
+        Some(Hit {
+            normal: Normal::new(Vec3::Z),
+            position: Point(pos),
+            t,
+        })
+    } else {
+        None
+    }
+}
+
+// Sector of the annulus stretched by the transform of its plane
+pub fn area(m: Mat3, inner_radius: f32, theta_min: f32, theta_max: f32) -> f32 {
+    0.5 * (theta_max - theta_min)
+        * (1. - inner_radius * inner_radius)
+        * m.x_axis.cross(m.y_axis).length()
+}
+
+// Fractions of the way from the inner to the outer radius and of the sweep
+pub fn bin_coordinates(
+    point: Vec3,
+    inner_radius: f32,
+    theta_min: f32,
+    theta_max: f32,
+) -> (f32, f32) {
+    let u = (point.length() - inner_radius) / (1. - inner_radius);
+    (u, sweep_fraction(point, theta_min, theta_max))
+}
+
+pub fn bin_area(
+    m: Mat3,
+    inner_radius: f32,
+    theta_min: f32,
+    theta_max: f32,
+    u: Range<f32>,
+    v: Range<f32>,
+) -> f32 {
+    let (theta_min, theta_max) = sweep_range(theta_min, theta_max, v);
+    let radius = |u: f32| inner_radius + u * (1. - inner_radius);
+
+    area(m, radius(u.start), theta_min, theta_max) - area(m, radius(u.end), theta_min, theta_max)
+}
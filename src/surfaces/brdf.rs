@@ -0,0 +1,401 @@
+use std::{
+    f32::consts::{FRAC_PI_2, PI},
+    fmt, fs, io,
+    path::Path,
+};
+
+use glam::Vec3;
+use rand::Rng;
+
+use super::{DirectionalEmissivity, EmissivityModel};
+use crate::Normal;
+
+// Measurements are rarely exact, so reflectances this close to 1 still count as conserving energy
+const ENERGY_TOLERANCE: f32 = 1e-3;
+
+/// Isotropic bidirectional reflectance distribution function (1/sr) tabulated on a grid of
+/// incidence angle, reflection angle, and reflection azimuth. Azimuth is measured from the plane
+/// of incidence, with 0 on the far side along the mirror direction, and the table is mirrored
+/// across that plane.
+#[derive(Clone, Debug)]
+pub struct TabulatedBrdf {
+    incidence_angles: Vec<f32>,
+    reflection_angles: Vec<f32>,
+    azimuth_angles: Vec<f32>,
+    // Fraction of the energy reflected at each incidence angle and its distribution over the cells
+    // between the reflection and azimuth angles, azimuth varying fastest
+    reflectances: Vec<f32>,
+    cumulative: Vec<Vec<f32>>,
+    emissivity: DirectionalEmissivity,
+}
+
+impl TabulatedBrdf {
+    /// Angles are in radians and increasing. Reflection angles must run from 0 to pi/2 and azimuths
+    /// from 0 to pi, unless there is a single azimuth, which makes the table independent of
+    /// azimuth. Values are indexed by incidence, then reflection, then azimuth angle.
+    pub fn new(
+        incidence_angles: Vec<f32>,
+        reflection_angles: Vec<f32>,
+        azimuth_angles: Vec<f32>,
+        values: Vec<f32>,
+    ) -> Result<Self, BrdfError> {
+        validate_angles("incidence", &incidence_angles, 0.0, FRAC_PI_2, false)?;
+        validate_angles("reflection", &reflection_angles, 0.0, FRAC_PI_2, true)?;
+        if azimuth_angles.len() != 1 {
+            validate_angles("azimuth", &azimuth_angles, 0.0, PI, true)?;
+        }
+
+        let row_size = reflection_angles.len() * azimuth_angles.len();
+        if values.len() != incidence_angles.len() * row_size {
+            return Err(BrdfError::Grid(format!(
+                "expected {} values but found {}",
+                incidence_angles.len() * row_size,
+                values.len()
+            )));
+        }
+        if values.iter().any(|value| value.is_nan() || *value < 0.0) {
+            return Err(BrdfError::Grid("values must not be negative".into()));
+        }
+
+        let mut reflectances = Vec::with_capacity(incidence_angles.len());
+        let mut cumulative = Vec::with_capacity(incidence_angles.len());
+
+        for (row, &incidence_angle) in values.chunks(row_size).zip(&incidence_angles) {
+            let cells = cell_energies(row, &reflection_angles, &azimuth_angles);
+
+            let mut total = 0.0;
+            let mut row_cumulative: Vec<f32> = cells
+                .iter()
+                .map(|energy| {
+                    total += *energy as f64;
+                    total as f32
+                })
+                .collect();
+
+            // The cells of a row add up to the directional hemispherical reflectance at its
+            // incidence angle, which cannot be more than 1. Excesses within the tolerance are
+            // clamped to 1, so those rows reflect everything.
+            let reflectance = total as f32;
+            if reflectance > 1.0 + ENERGY_TOLERANCE {
+                return Err(BrdfError::NotEnergyConserving {
+                    incidence_angle,
+                    reflectance,
+                });
+            }
+
+            // Cumulative fractions of the row's reflected energy, for picking a cell by energy
+            if total > 0.0 {
+                for value in &mut row_cumulative {
+                    *value /= reflectance;
+                }
+            }
+
+            reflectances.push(reflectance.min(1.0));
+            cumulative.push(row_cumulative);
+        }
+
+        // By Kirchhoff's law whatever is not reflected is absorbed, and emitted in that direction
+        let emissivity = DirectionalEmissivity::new(EmissivityModel::Table(
+            incidence_angles
+                .iter()
+                .zip(&reflectances)
+                .map(|(&angle, &reflectance)| (angle, 1.0 - reflectance))
+                .collect(),
+        ));
+
+        Ok(Self {
+            incidence_angles,
+            reflection_angles,
+            azimuth_angles,
+            reflectances,
+            cumulative,
+            emissivity,
+        })
+    }
+
+    /// Reads a table with one "incidence reflection azimuth value" row per grid point, angles in
+    /// degrees. Rows may be in any order and '#' starts a comment.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, BrdfError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, BrdfError> {
+        let mut rows = Vec::new();
+
+        for (line_index, line) in source.lines().enumerate() {
+            let line_number = line_index + 1;
+            let parse_error = |message: String| BrdfError::Parse {
+                line: line_number,
+                message,
+            };
+
+            // Strip comments
+            let line = line.split('#').next().unwrap_or_default();
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.is_empty() {
+                continue;
+            }
+            if tokens.len() != 4 {
+                return Err(parse_error(format!(
+                    "expected 4 columns but found {}",
+                    tokens.len()
+                )));
+            }
+
+            let mut columns = [0.0f32; 4];
+            for (column, token) in columns.iter_mut().zip(&tokens) {
+                *column = token
+                    .parse()
+                    .map_err(|_| parse_error(format!("invalid number '{token}'")))?;
+            }
+            rows.push(columns);
+        }
+
+        let grid = |column: usize| {
+            let mut angles: Vec<f32> = rows.iter().map(|row| row[column]).collect();
+            angles.sort_by(f32::total_cmp);
+            angles.dedup();
+            angles
+        };
+        let incidence_degrees = grid(0);
+        let reflection_degrees = grid(1);
+        let azimuth_degrees = grid(2);
+
+        let index = |angles: &[f32], angle: f32| angles.partition_point(|&a| a < angle);
+        let num_reflection = reflection_degrees.len();
+        let num_azimuth = azimuth_degrees.len();
+
+        let mut values = vec![None; incidence_degrees.len() * num_reflection * num_azimuth];
+        for [incidence, reflection, azimuth, value] in rows {
+            let i = index(&incidence_degrees, incidence);
+            let r = index(&reflection_degrees, reflection);
+            let p = index(&azimuth_degrees, azimuth);
+            let slot = &mut values[(i * num_reflection + r) * num_azimuth + p];
+            if slot.replace(value).is_some() {
+                return Err(BrdfError::Grid(format!(
+                    "more than one value at ({incidence}, {reflection}, {azimuth})"
+                )));
+            }
+        }
+
+        let values = values
+            .into_iter()
+            .collect::<Option<Vec<f32>>>()
+            .ok_or_else(|| BrdfError::Grid("table does not fill its grid".into()))?;
+
+        let radians = |angles: Vec<f32>| angles.into_iter().map(f32::to_radians).collect();
+        Self::new(
+            radians(incidence_degrees),
+            radians(reflection_degrees),
+            radians(azimuth_degrees),
+            values,
+        )
+    }
+
+    // Fraction of the energy arriving along a direction with the given cosine to the normal that is
+    // reflected
+    pub fn reflectance(&self, cos_theta: f32) -> f32 {
+        let (row, fraction) = self.incidence_position(cos_theta);
+        match fraction {
+            Some(fraction) => {
+                self.reflectances[row]
+                    + fraction * (self.reflectances[row + 1] - self.reflectances[row])
+            }
+            None => self.reflectances[row],
+        }
+    }
+
+    // Emissivity in each direction, which is what the table does not reflect
+    pub fn emissivity(&self) -> &DirectionalEmissivity {
+        &self.emissivity
+    }
+
+    // Reflected direction for an incoming direction, with density proportional to the BRDF times
+    // the cosine to the normal, interpolated between the tabulated incidence angles
+    pub(crate) fn sample_direction(
+        &self,
+        normal: &Normal,
+        direction: Vec3,
+        rng: &mut impl Rng,
+    ) -> Vec3 {
+        let direction = direction.normalize();
+        let (mut row, fraction) = self.incidence_position(-direction.dot(normal.0));
+
+        // Pick between neighbouring rows in proportion to how much each reflects
+        if let Some(fraction) = fraction {
+            let lower = (1.0 - fraction) * self.reflectances[row];
+            let upper = fraction * self.reflectances[row + 1];
+            if lower + upper > 0.0 && rng.gen_range(0.0..1.0) * (lower + upper) >= lower {
+                row += 1;
+            }
+        }
+
+        // Nothing is reflected, so the direction carries no energy
+        if self.reflectances[row] == 0.0 {
+            return normal.to_diffuse_ray(rng);
+        }
+
+        // Cell with chance proportional to the energy it reflects
+        let cumulative = &self.cumulative[row];
+        let u = rng.gen_range(0.0..1.0f32);
+        let cell = cumulative
+            .partition_point(|&value| value <= u)
+            .min(cumulative.len() - 1);
+
+        let num_azimuth_cells = self.azimuth_angles.len().saturating_sub(1).max(1);
+        let (r, p) = (cell / num_azimuth_cells, cell % num_azimuth_cells);
+
+        // Cosine weighted within the cell, so uniform in sin^2 of the reflection angle
+        let sin_squared_min = self.reflection_angles[r].sin().powi(2);
+        let sin_squared_max = self.reflection_angles[r + 1].sin().powi(2);
+        let sin_squared =
+            sin_squared_min + rng.gen_range(0.0..1.0f32) * (sin_squared_max - sin_squared_min);
+        let (azimuth_min, azimuth_max) = match self.azimuth_angles.len() {
+            1 => (0.0, PI),
+            _ => (self.azimuth_angles[p], self.azimuth_angles[p + 1]),
+        };
+        // Uniform in azimuth within the cell, on either side of the plane of incidence since the
+        // table is mirrored across it
+        let mut phi = azimuth_min + rng.gen_range(0.0..1.0f32) * (azimuth_max - azimuth_min);
+        if rng.gen_bool(0.5) {
+            phi = -phi;
+        }
+
+        // Zero azimuth is along the incoming direction's path across the surface
+        let tangential = direction - direction.dot(normal.0) * normal.0;
+        let t1 = if tangential.length_squared() > 1e-12 {
+            tangential.normalize()
+        } else {
+            normal.0.any_orthonormal_vector()
+        };
+        let t2 = normal.0.cross(t1);
+
+        let sin_theta = sin_squared.sqrt();
+        let cos_theta = (1.0 - sin_squared).max(0.0).sqrt();
+
+        normal.0 * cos_theta + (t1 * phi.cos() + t2 * phi.sin()) * sin_theta
+    }
+
+    // Row of the incidence angle at or below the given direction, and how far it is towards the
+    // next row if it lies between two
+    fn incidence_position(&self, cos_theta: f32) -> (usize, Option<f32>) {
+        let theta = cos_theta.clamp(0.0, 1.0).acos();
+        let i = self
+            .incidence_angles
+            .partition_point(|&angle| angle <= theta);
+
+        if i == 0 {
+            (0, None)
+        } else if i == self.incidence_angles.len() {
+            (i - 1, None)
+        } else {
+            let (theta_0, theta_1) = (self.incidence_angles[i - 1], self.incidence_angles[i]);
+            (i - 1, Some((theta - theta_0) / (theta_1 - theta_0)))
+        }
+    }
+}
+
+// Reflected energy through each cell between neighbouring grid angles, taking the BRDF as the mean
+// of the cell's corners. Each cell is counted on both sides of the plane of incidence.
+fn cell_energies(row: &[f32], reflection_angles: &[f32], azimuth_angles: &[f32]) -> Vec<f32> {
+    let num_azimuth = azimuth_angles.len();
+    let value = |r: usize, p: usize| row[r * num_azimuth + p];
+
+    let mut energies = Vec::new();
+    for r in 0..reflection_angles.len() - 1 {
+        // Integral of cos(theta) sin(theta) over the reflection angles of the cell
+        let projected =
+            0.5 * (reflection_angles[r + 1].sin().powi(2) - reflection_angles[r].sin().powi(2));
+
+        if num_azimuth == 1 {
+            let mean = 0.5 * (value(r, 0) + value(r + 1, 0));
+            energies.push(2.0 * PI * mean * projected);
+            continue;
+        }
+
+        for p in 0..num_azimuth - 1 {
+            let mean =
+                0.25 * (value(r, p) + value(r, p + 1) + value(r + 1, p) + value(r + 1, p + 1));
+            let width = azimuth_angles[p + 1] - azimuth_angles[p];
+            energies.push(2.0 * width * mean * projected);
+        }
+    }
+    energies
+}
+
+fn validate_angles(
+    name: &str,
+    angles: &[f32],
+    min: f32,
+    max: f32,
+    spans_range: bool,
+) -> Result<(), BrdfError> {
+    let grid_error = |message: String| Err(BrdfError::Grid(format!("{name} angles {message}")));
+
+    if angles.len() < if spans_range { 2 } else { 1 } {
+        return grid_error("are missing".into());
+    }
+    if !angles.windows(2).all(|pair| pair[0] < pair[1]) {
+        return grid_error("must be increasing".into());
+    }
+
+    // Allow for rounding of angles converted from degrees
+    const SLACK: f32 = 1e-4;
+    let (first, last) = (angles[0], angles[angles.len() - 1]);
+    if first < min - SLACK || last > max + SLACK {
+        return grid_error(format!("must be between {min} and {max}"));
+    }
+    if spans_range && (first > min + SLACK || last < max - SLACK) {
+        return grid_error(format!("must run from {min} to {max}"));
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum BrdfError {
+    Io(io::Error),
+    Parse {
+        line: usize,
+        message: String,
+    },
+    Grid(String),
+    NotEnergyConserving {
+        incidence_angle: f32,
+        reflectance: f32,
+    },
+}
+
+impl fmt::Display for BrdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BrdfError::Io(error) => write!(f, "failed to read BRDF table: {error}"),
+            BrdfError::Parse { line, message } => {
+                write!(f, "failed to parse BRDF table on line {line}: {message}")
+            }
+            BrdfError::Grid(message) => write!(f, "invalid BRDF grid: {message}"),
+            BrdfError::NotEnergyConserving {
+                incidence_angle,
+                reflectance,
+            } => write!(
+                f,
+                "BRDF reflects {reflectance} of the energy arriving {} degrees from the normal",
+                incidence_angle.to_degrees()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BrdfError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BrdfError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BrdfError {
+    fn from(error: io::Error) -> Self {
+        BrdfError::Io(error)
+    }
+}
@@ -1,8 +1,11 @@
+use std::ops::Range;
+
+use super::{in_sweep, integrate_angle, sweep_fraction, sweep_range};
 use crate::{Hit, Normal, Point, Ray, SurfaceSample};
-use glam::Vec3;
-use rand::{thread_rng, Rng};
+use glam::{Mat3, Vec3};
+use rand::Rng;
 
-pub fn intersect(ray: &Ray) -> Option<Hit> {
+pub fn intersect(ray: &Ray, theta_min: f32, theta_max: f32, z_max: f32) -> Option<Hit> {
     let x0 = ray.origin.0.x;
     let y0 = ray.origin.0.y;
     let z0 = ray.origin.0.z;
@@ -26,43 +29,37 @@ pub fn intersect(ray: &Ray) -> Option<Hit> {
     let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
     let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
 
-    if t1 > 0.0001 {
-        let position = Point(ray.at(t1));
-        if position.0.z > 0.0 && position.0.z < 1.0 {
-            let normal =
-                Normal::new(Vec3::new(position.0.x, position.0.y, 1. - position.0.z).normalize());
-            return Some(Hit {
-                normal,
-                position,
-                t: t1,
-            });
+    // Take the nearest hit on the cone below the truncation height, rather than the mirrored cone
+    [t1.min(t2), t1.max(t2)].into_iter().find_map(|t| {
+        if t < 0.0001 {
+            return None;
         }
-    }
 
-    if t2 > 0.0001 {
-        let position = Point(ray.at(t2));
-        if position.0.z > 0.0 && position.0.z < 1.0 {
-            let normal =
-                Normal::new(Vec3::new(position.0.x, position.0.y, 1. - position.0.z).normalize());
-            return Some(Hit {
-                normal,
-                position,
-                t: t2,
-            });
+        let position = Point(ray.at(t));
+        if position.0.z <= 0.0
+            || position.0.z >= z_max
+            || !in_sweep(position.0, theta_min, theta_max)
+        {
+            return None;
         }
-    }
 
-    None
+        let normal =
+            Normal::new(Vec3::new(position.0.x, position.0.y, 1. - position.0.z).normalize());
+        Some(Hit {
+            normal,
+            position,
+            t,
+        })
+    })
 }
 
-pub fn sample() -> SurfaceSample {
-    let mut rng = thread_rng();
-
+pub fn sample(rng: &mut impl Rng, theta_min: f32, theta_max: f32, z_max: f32) -> SurfaceSample {
     // Generate a random angle in radians
-    let theta = rng.gen_range(0.0..std::f32::consts::TAU);
+    let theta = rng.gen_range(theta_min..theta_max);
 
-    // Generate a random value from a uniform distribution
-    let r = (rng.gen_range(0.0..1.0) as f32).sqrt();
+    // Generate a radius with density proportional to r, above the truncation radius
+    let r_min = 1. - z_max;
+    let r = (r_min * r_min + rng.gen_range(0.0..1.0) as f32 * (1. - r_min * r_min)).sqrt();
 
     // Calculate the x and y coordinates of the point on the surface of the cone
     let x = r * theta.cos();
@@ -78,3 +75,36 @@ pub fn sample() -> SurfaceSample {
         normal: Normal::new(normal),
     }
 }
+
+// Each ring at radius r contributes r times the transformed tangents, which integrates to
+// (1 - r_min^2) / 2 between the truncation radius and the base
+pub fn area(m: Mat3, theta_min: f32, theta_max: f32, z_max: f32) -> f32 {
+    let r_min = 1. - z_max;
+
+    let unit_area = integrate_angle(theta_min, theta_max, |theta| {
+        let slant = m * Vec3::new(theta.cos(), theta.sin(), -1.0);
+        let tangent = m * Vec3::new(-theta.sin(), theta.cos(), 0.0);
+        slant.cross(tangent).length()
+    });
+
+    0.5 * (1. - r_min * r_min) * unit_area
+}
+
+// Fractions of the sweep and of the height up to the truncation
+pub fn bin_coordinates(point: Vec3, theta_min: f32, theta_max: f32, z_max: f32) -> (f32, f32) {
+    (sweep_fraction(point, theta_min, theta_max), point.z / z_max)
+}
+
+// Area between two heights is the difference of the frustums up to each of them
+pub fn bin_area(
+    m: Mat3,
+    theta_min: f32,
+    theta_max: f32,
+    z_max: f32,
+    u: Range<f32>,
+    v: Range<f32>,
+) -> f32 {
+    let (theta_min, theta_max) = sweep_range(theta_min, theta_max, u);
+
+    area(m, theta_min, theta_max, v.end * z_max) - area(m, theta_min, theta_max, v.start * z_max)
+}
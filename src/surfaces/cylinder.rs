@@ -1,8 +1,11 @@
+use std::ops::Range;
+
+use super::{in_sweep, integrate_angle, sweep_fraction, sweep_range};
 use crate::{Hit, Normal, Point, Ray, SurfaceSample};
-use glam::Vec3;
-use rand::{thread_rng, Rng};
+use glam::{Mat3, Vec3};
+use rand::Rng;
 
-pub fn intersect(ray: &Ray) -> Option<Hit> {
+pub fn intersect(ray: &Ray, theta_min: f32, theta_max: f32, z_min: f32, z_max: f32) -> Option<Hit> {
     let a = ray.direction.x.powi(2) + ray.direction.y.powi(2);
     let b: f32 = 2.0 * (ray.origin.0.x * ray.direction.x + ray.origin.0.y * ray.direction.y);
     let c = ray.origin.0.x.powi(2) + ray.origin.0.y.powi(2) - 1.0;
@@ -16,42 +19,82 @@ pub fn intersect(ray: &Ray) -> Option<Hit> {
         return None;
     }
 
-    let t1 = (-b + discriminant.sqrt()) / (2.0 * a);
-    let t2 = (-b - discriminant.sqrt()) / (2.0 * a);
+    let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
+    let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
 
-    // Get the smallest non-zero t
-    let mut t = t1;
-    if t < 0.0001 {
-        t = t2;
-    }
-    if t < 0.0001 {
-        return None;
-    }
+    // Take the nearest hit that lies within the limits of the wall
+    [t1, t2].into_iter().find_map(|t| {
+        if t < 0.0001 {
+            return None;
+        }
 
-    let position = ray.origin.0 + t * ray.direction;
-    if (position.z < -0.5) || (position.z > 0.5) {
-        return None;
-    }
+        let position = ray.at(t);
+        if position.z < z_min || position.z > z_max || !in_sweep(position, theta_min, theta_max) {
+            return None;
+        }
 
-    let normal = Normal::new_from_unnormalized(Vec3::new(position.x, position.y, 0.0));
+        let normal = Normal::new_from_unnormalized(Vec3::new(position.x, position.y, 0.0));
 
-    Some(Hit {
-        normal,
-        position: Point(position),
-        t,
+        Some(Hit {
+            normal,
+            position: Point(position),
+            t,
+        })
     })
 }
 
-pub fn sample() -> SurfaceSample {
-    let mut rng = thread_rng();
-
-    let theta = rng.gen_range(0.0..std::f32::consts::TAU);
+pub fn sample(
+    rng: &mut impl Rng,
+    theta_min: f32,
+    theta_max: f32,
+    z_min: f32,
+    z_max: f32,
+) -> SurfaceSample {
+    let theta = rng.gen_range(theta_min..theta_max);
     let x = theta.cos();
     let y = theta.sin();
 
-    let z = rng.gen_range(-0.5..0.5);
+    let z = rng.gen_range(z_min..z_max);
 
     let position = Point(Vec3 { x, y, z });
     let normal = Normal::new(Vec3::new(x, y, 0.0));
     SurfaceSample { position, normal }
 }
+
+// Integrates the length of the transformed tangents around the unit height side wall, which also
+// handles elliptical cross sections and shear
+pub fn area(m: Mat3, theta_min: f32, theta_max: f32, z_min: f32, z_max: f32) -> f32 {
+    let unit_height_area = integrate_angle(theta_min, theta_max, |theta| {
+        let tangent = m * Vec3::new(-theta.sin(), theta.cos(), 0.0);
+        tangent.cross(m.z_axis).length()
+    });
+
+    unit_height_area * (z_max - z_min)
+}
+
+// Fractions of the sweep and of the way from z_min to z_max
+pub fn bin_coordinates(
+    point: Vec3,
+    theta_min: f32,
+    theta_max: f32,
+    z_min: f32,
+    z_max: f32,
+) -> (f32, f32) {
+    let u = sweep_fraction(point, theta_min, theta_max);
+    (u, (point.z - z_min) / (z_max - z_min))
+}
+
+pub fn bin_area(
+    m: Mat3,
+    theta_min: f32,
+    theta_max: f32,
+    z_min: f32,
+    z_max: f32,
+    u: Range<f32>,
+    v: Range<f32>,
+) -> f32 {
+    let (theta_min, theta_max) = sweep_range(theta_min, theta_max, u);
+    let z = |v: f32| z_min + v * (z_max - z_min);
+
+    area(m, theta_min, theta_max, z(v.start), z(v.end))
+}
@@ -1,11 +1,13 @@
+use std::ops::Range;
+
+use super::{in_sweep, sweep_fraction, sweep_range};
 use crate::{Hit, Normal, Point, Ray, SurfaceSample};
 
-use glam::Vec3;
-use rand::{thread_rng, Rng};
+use glam::{Mat3, Vec3};
+use rand::Rng;
 
-pub fn sample() -> SurfaceSample {
-    let mut rng = thread_rng();
-    let theta = rng.gen_range(0.0..std::f32::consts::TAU);
+pub fn sample(rng: &mut impl Rng, theta_min: f32, theta_max: f32) -> SurfaceSample {
+    let theta = rng.gen_range(theta_min..theta_max);
     let r = (rng.gen_range(0.0..1.0) as f32).sqrt();
 
     let x = r * theta.cos();
@@ -17,7 +19,7 @@ pub fn sample() -> SurfaceSample {
     }
 }
 
-pub fn intersect(ray: &Ray) -> Option<Hit> {
+pub fn intersect(ray: &Ray, theta_min: f32, theta_max: f32) -> Option<Hit> {
     // Ensure ray intersects plane
     if ray.direction.z == 0.0 {
         return None;
@@ -34,7 +36,7 @@ pub fn intersect(ray: &Ray) -> Option<Hit> {
 
     let pos = ray.at(t);
 
-    if pos.dot(pos) < 1. {
+    if pos.dot(pos) < 1. && in_sweep(pos, theta_min, theta_max) {
         Some(Hit {
             normal: Normal::new(Vec3::Z),
             position: Point(pos),
@@ -44,3 +46,18 @@ pub fn intersect(ray: &Ray) -> Option<Hit> {
         None
     }
 }
+
+// Sector of the unit disk stretched by the transform of its plane
+pub fn area(m: Mat3, theta_min: f32, theta_max: f32) -> f32 {
+    0.5 * (theta_max - theta_min) * m.x_axis.cross(m.y_axis).length()
+}
+
+// Fractions of the radius and of the sweep
+pub fn bin_coordinates(point: Vec3, theta_min: f32, theta_max: f32) -> (f32, f32) {
+    (point.length(), sweep_fraction(point, theta_min, theta_max))
+}
+
+pub fn bin_area(m: Mat3, theta_min: f32, theta_max: f32, u: Range<f32>, v: Range<f32>) -> f32 {
+    let (theta_min, theta_max) = sweep_range(theta_min, theta_max, v);
+    area(m, theta_min, theta_max) * (u.end * u.end - u.start * u.start)
+}
@@ -0,0 +1,239 @@
+use std::{
+    f32::consts::{FRAC_PI_2, TAU},
+    fmt,
+};
+
+use glam::Vec3;
+use rand::Rng;
+
+use crate::Normal;
+
+// Emissivity is tabulated at evenly spaced angles from the normal for absorption, and at evenly
+// spaced values of sin^2 of the angle, which is uniform for diffuse emission, for sampling
+const NUM_SEGMENTS: usize = 1024;
+
+/// How emissivity varies with the angle from the surface normal
+#[derive(Clone, Debug)]
+pub enum EmissivityModel {
+    /// Pairs of angle from the normal (radians) and emissivity, with angles increasing. Emissivity
+    /// is interpolated linearly between the points and held constant beyond them.
+    Table(Vec<(f32, f32)>),
+    /// Fresnel absorption of a smooth dielectric with the given refractive index
+    Dielectric { refractive_index: f32 },
+    /// Fresnel absorption of a smooth metal with a complex refractive index n + ik
+    Metal {
+        refractive_index: f32,
+        extinction_coefficient: f32,
+    },
+}
+
+impl EmissivityModel {
+    // Emissivity along a direction at the given angle from the normal
+    fn evaluate(&self, theta: f32) -> f32 {
+        match self {
+            EmissivityModel::Table(points) => interpolate(points, theta),
+            EmissivityModel::Dielectric { refractive_index } => {
+                1.0 - fresnel_reflectance(*refractive_index as f64, 0.0, theta as f64) as f32
+            }
+            EmissivityModel::Metal {
+                refractive_index,
+                extinction_coefficient,
+            } => {
+                let reflectance = fresnel_reflectance(
+                    *refractive_index as f64,
+                    *extinction_coefficient as f64,
+                    theta as f64,
+                );
+                1.0 - reflectance as f32
+            }
+        }
+    }
+
+    fn validate(&self) {
+        match self {
+            EmissivityModel::Table(points) => {
+                assert!(!points.is_empty(), "Emissivity table must not be empty");
+                assert!(
+                    points.windows(2).all(|pair| pair[0].0 < pair[1].0),
+                    "Emissivity table angles must be increasing"
+                );
+                assert!(
+                    points.iter().all(|(_, e)| (0.0..=1.0).contains(e)),
+                    "Emissivity must be between 0 and 1"
+                );
+            }
+            EmissivityModel::Dielectric { refractive_index } => {
+                assert!(*refractive_index > 0.0, "Refractive index must be positive");
+            }
+            EmissivityModel::Metal {
+                refractive_index,
+                extinction_coefficient,
+            } => {
+                assert!(*refractive_index > 0.0, "Refractive index must be positive");
+                assert!(
+                    *extinction_coefficient >= 0.0,
+                    "Extinction coefficient must not be negative"
+                );
+            }
+        }
+    }
+}
+
+/// Emissivity that depends on direction, which by Kirchhoff's law is also the absorptivity for
+/// rays arriving from that direction
+#[derive(Clone, Debug)]
+pub struct DirectionalEmissivity {
+    // Emissivity at the ends of each segment of angle from the normal
+    values: Vec<f32>,
+    // Fraction of the emitted energy below the end of each segment of sin^2 of the angle
+    cumulative: Vec<f32>,
+    hemispherical: f32,
+}
+
+impl DirectionalEmissivity {
+    pub fn new(model: EmissivityModel) -> Self {
+        model.validate();
+
+        let values = (0..=NUM_SEGMENTS)
+            .map(|i| model.evaluate(FRAC_PI_2 * i as f32 / NUM_SEGMENTS as f32))
+            .collect();
+
+        let emission: Vec<f32> = (0..=NUM_SEGMENTS)
+            .map(|i| {
+                let sin_squared = i as f32 / NUM_SEGMENTS as f32;
+                model.evaluate(sin_squared.sqrt().asin())
+            })
+            .collect();
+
+        // Diffuse emission is uniform in sin^2, so each segment emits in proportion to its mean
+        let mut total = 0.0;
+        let mut cumulative: Vec<f32> = emission
+            .windows(2)
+            .map(|pair| {
+                total += 0.5 * (pair[0] + pair[1]) as f64;
+                total as f32
+            })
+            .collect();
+
+        let hemispherical = (total / NUM_SEGMENTS as f64) as f32;
+        if total > 0.0 {
+            for value in &mut cumulative {
+                *value /= total as f32;
+            }
+        }
+
+        Self {
+            values,
+            cumulative,
+            hemispherical,
+        }
+    }
+
+    // Emissivity and absorptivity along a direction with the given cosine to the normal
+    pub fn at(&self, cos_theta: f32) -> f32 {
+        let theta = cos_theta.clamp(0.0, 1.0).acos();
+        let position = theta / FRAC_PI_2 * NUM_SEGMENTS as f32;
+        let i = (position as usize).min(NUM_SEGMENTS - 1);
+        let fraction = position - i as f32;
+
+        self.values[i] + fraction * (self.values[i + 1] - self.values[i])
+    }
+
+    // Emissivity averaged over the hemisphere, weighted by the cosine of the angle from the normal
+    pub fn hemispherical(&self) -> f32 {
+        self.hemispherical
+    }
+
+    // Largest emissivity in any direction
+    pub fn maximum(&self) -> f32 {
+        self.values.iter().copied().fold(0.0, f32::max)
+    }
+
+    // Direction with density proportional to the emissivity times the cosine to the normal
+    pub(crate) fn sample_direction(&self, normal: &Normal, rng: &mut impl Rng) -> Vec3 {
+        let u = rng.gen_range(0.0..1.0f32);
+        let phi = rng.gen_range(0.0..TAU);
+
+        // Invert the cumulative distribution, taking the density as constant within each segment
+        let i = self
+            .cumulative
+            .partition_point(|&value| value <= u)
+            .min(NUM_SEGMENTS - 1);
+        let start = if i == 0 { 0.0 } else { self.cumulative[i - 1] };
+        let width = self.cumulative[i] - start;
+        let fraction = if width > 0.0 {
+            (u - start) / width
+        } else {
+            0.5
+        };
+        let sin_squared = ((i as f32 + fraction) / NUM_SEGMENTS as f32).min(1.0);
+
+        let sin_theta = sin_squared.sqrt();
+        let cos_theta = (1.0 - sin_squared).sqrt();
+
+        let t1 = normal.0.any_orthonormal_vector();
+        let t2 = t1.cross(normal.0).normalize();
+
+        normal.0 * cos_theta + (t1 * phi.cos() + t2 * phi.sin()) * sin_theta
+    }
+}
+
+fn interpolate(points: &[(f32, f32)], theta: f32) -> f32 {
+    let theta = theta.clamp(0.0, FRAC_PI_2);
+    let i = points.partition_point(|&(angle, _)| angle <= theta);
+
+    if i == 0 {
+        points[0].1
+    } else if i == points.len() {
+        points[i - 1].1
+    } else {
+        let (theta_0, e_0) = points[i - 1];
+        let (theta_1, e_1) = points[i];
+        e_0 + (e_1 - e_0) * (theta - theta_0) / (theta_1 - theta_0)
+    }
+}
+
+// Unpolarized reflectance of a smooth interface from vacuum into a medium with complex refractive
+// index n + ik, at angle theta from the normal
+// Source: Fresnel's relations for an absorbing medium, e.g. M. F. Modest, Radiative Heat Transfer
+fn fresnel_reflectance(n: f64, k: f64, theta: f64) -> f64 {
+    let cos = theta.cos().max(0.0);
+    let sin_squared = 1.0 - cos * cos;
+    if cos == 0.0 {
+        return 1.0;
+    }
+
+    let c = n * n - k * k - sin_squared;
+    let root = (c * c + 4.0 * n * n * k * k).sqrt();
+    let a = (0.5 * (root + c)).max(0.0).sqrt();
+    let b_squared = (0.5 * (root - c)).max(0.0);
+
+    let s = a * a + b_squared;
+    let r_s = (s - 2.0 * a * cos + cos * cos) / (s + 2.0 * a * cos + cos * cos);
+
+    let sin_tan = sin_squared / cos;
+    let r_p = r_s * (s - 2.0 * a * sin_tan + sin_tan * sin_tan)
+        / (s + 2.0 * a * sin_tan + sin_tan * sin_tan);
+
+    0.5 * (r_s + r_p)
+}
+
+/// Why a surface cannot have an emissivity of its own in a spectral band
+#[derive(Debug)]
+pub enum BandError {
+    /// A BRDF table fixes what the surface absorbs, so bands cannot change it
+    Brdf { band: usize },
+}
+
+impl fmt::Display for BandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BandError::Brdf { band } => write!(
+                f,
+                "band {band} cannot have its own emissivity on a surface with a BRDF"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BandError {}
@@ -1,65 +1,332 @@
+mod annulus;
+mod brdf;
 mod cone;
 mod cylinder;
 mod disk;
+mod emissivity;
 mod rectangle;
 mod sphere;
+mod triangle;
 
-use glam::Vec3;
+use std::{cell::RefCell, f32::consts::TAU, ops::Range, rc::Rc, sync::Arc};
+
+use glam::{Mat3, Vec3};
 use kiss3d::{
-    nalgebra::{Quaternion, Translation3, UnitQuaternion, Vector3},
+    nalgebra::{Point3, Quaternion, Translation3, UnitQuaternion, Vector3},
+    resource::Mesh,
     window::Window,
 };
+use rand::Rng;
+
+pub use brdf::{BrdfError, TabulatedBrdf};
+pub use emissivity::{BandError, DirectionalEmissivity, EmissivityModel};
 
 use super::transform::Transform;
-use super::{Hit, Ray, SurfaceSample};
+use super::{Aabb, Hit, Normal, Point, Ray, SurfaceSample};
 
+// Angles are in radians about the local z axis, starting from the local x axis
 #[derive(Clone)]
 pub enum Collider {
     Sphere,
-    Disk,
-    Cylinder,
+    Disk {
+        theta_min: f32,
+        theta_max: f32,
+    },
+    Cylinder {
+        theta_min: f32,
+        theta_max: f32,
+        z_min: f32,
+        z_max: f32,
+    },
     Rectangle,
-    Cone,
+    Cone {
+        theta_min: f32,
+        theta_max: f32,
+        z_max: f32,
+    },
+    Triangle,
+    Annulus {
+        inner_radius: f32,
+        theta_min: f32,
+        theta_max: f32,
+    },
 }
 
 #[derive(Clone)]
 pub enum Material {
     Diffuse,
     Specular,
+    // Each reflection is a mirror reflection with probability specularity, and diffuse otherwise
+    Mixed {
+        specularity: f32,
+    },
+    // Passes a fraction of the incident energy through the surface and reflects the rest of what
+    // is not absorbed like a mirror. Transmitted rays refract if there is a refractive index, which
+    // is that of the medium behind the surface relative to the medium in front of it.
+    Transmissive {
+        transmissivity: f32,
+        refractive_index: Option<f32>,
+    },
+    // Reflects into directions drawn from a tabulated BRDF, which also sets the absorptivity
+    Brdf(Arc<TabulatedBrdf>),
+}
+
+impl Optics {
+    // Fraction of the energy arriving along a direction with the given cosine to the normal that
+    // is absorbed
+    pub fn absorptivity(&self, cos_theta: f32) -> f32 {
+        match &self.directional {
+            Some(directional) => directional.at(cos_theta),
+            None => self.emissivity,
+        }
+    }
+
+    // Emitted directions follow the directional emissivity, so that emission and absorption keep
+    // reciprocity
+    pub fn emission_direction(&self, normal: &Normal, rng: &mut impl Rng) -> Vec3 {
+        match &self.directional {
+            Some(directional) => directional.sample_direction(normal, rng),
+            None => normal.to_diffuse_ray(rng),
+        }
+    }
+
+    fn validate(&self) {
+        assert!(
+            (0.0..=1.0).contains(&self.emissivity),
+            "Emissivity must be between 0 and 1"
+        );
+        let max_emissivity = self
+            .directional
+            .as_ref()
+            .map_or(self.emissivity, DirectionalEmissivity::maximum);
+
+        match &self.material {
+            Material::Mixed { specularity } => assert!(
+                (0.0..=1.0).contains(specularity),
+                "Specularity must be between 0 and 1"
+            ),
+            Material::Transmissive {
+                transmissivity,
+                refractive_index,
+            } => {
+                assert!(
+                    *transmissivity >= 0.0 && max_emissivity + transmissivity <= 1.0,
+                    "Emissivity and transmissivity must not add up to more than 1"
+                );
+                assert!(
+                    refractive_index.is_none_or(|index| index > 0.0),
+                    "Refractive index must be positive"
+                );
+            }
+            Material::Brdf(brdf) => assert!(
+                self.directional.as_ref().is_some_and(|directional| {
+                    (directional.hemispherical() - brdf.emissivity().hemispherical()).abs() < 1e-3
+                }) && (self.emissivity - brdf.emissivity().hemispherical()).abs() < 1e-3,
+                "Emissivity of a BRDF surface must be what its table does not reflect"
+            ),
+            Material::Diffuse | Material::Specular => {}
+        }
+    }
+}
+
+// The front of a surface faces along its normals
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Front,
+    Back,
+}
+
+// Sides of a surface that emit and interact with rays. By default a surface emits from its front
+// but still absorbs and reflects rays that hit its back, as surfaces did before they had sides.
+// Front and Back make the other side inactive, so rays that hit it are lost. Surfaces active on
+// both sides emit from each side in proportion to its emissivity, so their view factors are spread
+// over twice their area.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ActiveSides {
+    #[default]
+    FrontEmitting,
+    Front,
+    Back,
+    Both,
+}
+
+impl ActiveSides {
+    // Whether rays that hit the side interact with the surface
+    pub fn contains(&self, side: Side) -> bool {
+        matches!(
+            (self, side),
+            (ActiveSides::Both | ActiveSides::FrontEmitting, _)
+                | (ActiveSides::Front, Side::Front)
+                | (ActiveSides::Back, Side::Back)
+        )
+    }
+}
+
+// Optical properties of one side of a surface. With a directional emissivity, emissivity holds
+// its hemispherical value.
+#[derive(Clone)]
+pub struct Optics {
+    pub emissivity: f32,
+    pub material: Material,
+    pub directional: Option<DirectionalEmissivity>,
 }
 
 #[derive(Clone)]
 pub struct Surface {
     pub transform: Transform,
     collider: Collider,
-    pub emissivity: f32,
-    pub material: Material,
+    pub active_sides: ActiveSides,
+    pub front: Optics,
+    pub back: Optics,
+    // Whether the surface faces against the normals of its collider
+    flipped: bool,
+    // Number of sub-regions along each bin coordinate of the collider
+    bins: (usize, usize),
+    // Emissivity of both sides in each spectral band, where the band has its own
+    band_emissivities: Vec<Option<BandEmissivity>>,
+}
+
+// Emissivity a surface has of its own in a spectral band. With a directional emissivity,
+// emissivity holds its hemispherical value.
+#[derive(Clone)]
+struct BandEmissivity {
+    emissivity: f32,
+    directional: Option<DirectionalEmissivity>,
 }
 
 impl Surface {
     pub fn intersect(&self, ray: &Ray) -> Option<Hit> {
         let local_ray = self.transform.ray_world_to_local(ray.clone());
 
-        self.collider
-            .intersect(&local_ray)
-            .map(|hit| self.transform.hit_local_to_world(hit))
+        self.collider.intersect(&local_ray).map(|mut hit| {
+            if self.flipped {
+                hit.normal = Normal::new(-hit.normal.0);
+            }
+            self.transform.hit_local_to_world(hit)
+        })
     }
 
-    pub fn sample(&self) -> SurfaceSample {
-        let sample = self.collider.sample();
+    pub fn sample(&self, rng: &mut impl Rng) -> SurfaceSample {
+        let mut sample = self.collider.sample(rng);
+        if self.flipped {
+            sample.normal = Normal::new(-sample.normal.0);
+        }
 
         self.transform.surface_sample_local_to_world(sample)
     }
 
+    // World space area, including any non-uniform scale or shear in the transform
+    pub fn area(&self) -> f32 {
+        self.collider.area(Mat3::from_mat4(self.transform.m))
+    }
+
+    pub fn num_bins(&self) -> usize {
+        self.bins.0 * self.bins.1
+    }
+
+    // Bin containing a world space point on the surface. Bins are numbered along v first, so bin
+    // i * v_bins + j covers the i-th interval of u and the j-th interval of v.
+    pub fn bin(&self, position: &Point) -> usize {
+        let local = self.transform.point_world_to_local(position.clone());
+        let (u, v) = self.collider.bin_coordinates(local.0);
+
+        let index =
+            |fraction: f32, count: usize| ((fraction * count as f32) as usize).min(count - 1);
+        index(u, self.bins.0) * self.bins.1 + index(v, self.bins.1)
+    }
+
+    // World space area of every bin, in the same order as the bin numbers
+    pub fn bin_areas(&self) -> Vec<f32> {
+        let m = Mat3::from_mat4(self.transform.m);
+        let (u_bins, v_bins) = self.bins;
+        let interval =
+            |i: usize, count: usize| i as f32 / count as f32..(i + 1) as f32 / count as f32;
+
+        (0..u_bins)
+            .flat_map(|i| (0..v_bins).map(move |j| (interval(i, u_bins), interval(j, v_bins))))
+            .map(|(u, v)| self.collider.bin_area(m, u, v))
+            .collect()
+    }
+
+    // World space bounds, found by transforming the corners of the local space bounds
+    pub fn bounds(&self) -> Aabb {
+        Aabb::from_points(
+            self.collider
+                .local_bounds()
+                .corners()
+                .map(|corner| self.transform.m.transform_point3(corner)),
+        )
+    }
+
     pub fn add_debug_mesh(&self, window: &mut Window) {
+        // Partial shapes, triangles, and parallelograms are sheared or cut by their transform, so
+        // build their mesh directly in world space
+        match self.collider {
+            Collider::Sphere => {}
+            Collider::Triangle => {
+                self.add_debug_polygon(window, &[Vec3::ZERO, Vec3::X, Vec3::Y]);
+                return;
+            }
+            Collider::Rectangle if !self.transform.is_orthogonal() => {
+                self.add_debug_polygon(
+                    window,
+                    &[
+                        Vec3::new(-0.5, -0.5, 0.0),
+                        Vec3::new(0.5, -0.5, 0.0),
+                        Vec3::new(0.5, 0.5, 0.0),
+                        Vec3::new(-0.5, 0.5, 0.0),
+                    ],
+                );
+                return;
+            }
+            Collider::Rectangle => {}
+            Collider::Disk {
+                theta_min,
+                theta_max,
+            } => {
+                self.add_debug_sweep(window, theta_min, theta_max, |direction, v| v * direction);
+                return;
+            }
+            Collider::Cylinder {
+                theta_min,
+                theta_max,
+                z_min,
+                z_max,
+            } => {
+                self.add_debug_sweep(window, theta_min, theta_max, |direction, v| {
+                    direction + Vec3::Z * (z_min + v * (z_max - z_min))
+                });
+                return;
+            }
+            Collider::Cone {
+                theta_min,
+                theta_max,
+                z_max,
+            } => {
+                self.add_debug_sweep(window, theta_min, theta_max, |direction, v| {
+                    let z = v * z_max;
+                    (1. - z) * direction + Vec3::Z * z
+                });
+                return;
+            }
+            Collider::Annulus {
+                inner_radius,
+                theta_min,
+                theta_max,
+            } => {
+                self.add_debug_sweep(window, theta_min, theta_max, |direction, v| {
+                    (inner_radius + v * (1. - inner_radius)) * direction
+                });
+                return;
+            }
+        }
+
         let (scale, rotation, translation) = self.transform.m.to_scale_rotation_translation();
 
         let mut s = match self.collider {
             Collider::Sphere => window.add_sphere(scale.x),
-            Collider::Disk => window.add_cylinder(scale.x, 0.0001),
-            Collider::Cylinder => window.add_cylinder(scale.x, scale.z),
             Collider::Rectangle => window.add_quad(scale.x, scale.y, 1, 1),
-            Collider::Cone => window.add_cone(scale.x, scale.z),
+            _ => unreachable!(),
         };
 
         s.prepend_to_local_translation(&Translation3::new(
@@ -71,33 +338,87 @@ impl Surface {
         s.prepend_to_local_rotation(&UnitQuaternion::from_quaternion(Quaternion::new(
             rotation.w, rotation.x, rotation.y, rotation.z,
         )));
+    }
 
-        match self.collider {
-            Collider::Disk | Collider::Cylinder => {
-                s.prepend_to_local_rotation(
-                    &UnitQuaternion::rotation_between(&Vector3::y(), &Vector3::z()).unwrap(),
-                );
-            }
-            Collider::Cone => {
-                s.prepend_to_local_translation(&Translation3::new(0.0, 0.0, scale.z / 2.));
-                s.prepend_to_local_rotation(
-                    &UnitQuaternion::rotation_between(&Vector3::y(), &Vector3::z()).unwrap(),
-                );
-            }
-            _ => {}
-        };
+    // Adds a convex polygon given by its local space corners
+    fn add_debug_polygon(&self, window: &mut Window, local_corners: &[Vec3]) {
+        let faces = (1..local_corners.len() as u16 - 1)
+            .map(|i| Point3::new(0, i, i + 1))
+            .collect();
+
+        self.add_debug_triangles(window, local_corners, faces);
     }
 
-    pub fn new_sphere(position: Vec3, radius: f32) -> Self {
-        let transform = Transform::new(position, Vec3::ONE * radius, Vec3::Z, Vec3::X);
-        Surface {
-            transform,
-            collider: Collider::Sphere,
+    // Adds a strip swept about the local z axis, where point maps the unit direction at each
+    // angle and a parameter from 0 to 1 across the strip onto the surface
+    fn add_debug_sweep(
+        &self,
+        window: &mut Window,
+        theta_min: f32,
+        theta_max: f32,
+        point: impl Fn(Vec3, f32) -> Vec3,
+    ) {
+        const SEGMENTS: u16 = 64;
+
+        let local_points: Vec<Vec3> = (0..=SEGMENTS)
+            .flat_map(|i| {
+                let theta = theta_min + (theta_max - theta_min) * i as f32 / SEGMENTS as f32;
+                let direction = Vec3::new(theta.cos(), theta.sin(), 0.0);
+                [point(direction, 0.0), point(direction, 1.0)]
+            })
+            .collect();
+
+        let faces = (0..SEGMENTS)
+            .flat_map(|i| {
+                let (a, b, c, d) = (2 * i, 2 * i + 1, 2 * i + 2, 2 * i + 3);
+                [Point3::new(a, c, b), Point3::new(b, c, d)]
+            })
+            .collect();
+
+        self.add_debug_triangles(window, &local_points, faces);
+    }
+
+    fn add_debug_triangles(
+        &self,
+        window: &mut Window,
+        local_points: &[Vec3],
+        faces: Vec<Point3<u16>>,
+    ) {
+        let coords = local_points
+            .iter()
+            .map(|&point| self.transform.m.transform_point3(point))
+            .map(|point| Point3::new(point.x, point.y, point.z))
+            .collect();
+        let mesh = Mesh::new(coords, faces, None, None, false);
+
+        let mut s = window.add_mesh(Rc::new(RefCell::new(mesh)), Vector3::new(1., 1., 1.));
+        s.enable_backface_culling(false);
+    }
+
+    fn from_collider(transform: Transform, collider: Collider) -> Self {
+        let optics = Optics {
             emissivity: 1.0,
             material: Material::Diffuse,
+            directional: None,
+        };
+
+        Surface {
+            transform,
+            collider,
+            active_sides: ActiveSides::default(),
+            front: optics.clone(),
+            back: optics,
+            flipped: false,
+            bins: (1, 1),
+            band_emissivities: Vec::new(),
         }
     }
 
+    pub fn new_sphere(position: Vec3, radius: f32) -> Self {
+        let transform = Transform::new(position, Vec3::ONE * radius, Vec3::Z, Vec3::X);
+        Surface::from_collider(transform, Collider::Sphere)
+    }
+
     pub fn new_disk(position: Vec3, radius: f32, normal: Vec3) -> Self {
         let transform = Transform::new(
             position,
@@ -105,12 +426,13 @@ impl Surface {
             normal,
             normal.any_orthonormal_vector(),
         );
-        Surface {
+        Surface::from_collider(
             transform,
-            collider: Collider::Disk,
-            emissivity: 1.0,
-            material: Material::Diffuse,
-        }
+            Collider::Disk {
+                theta_min: 0.0,
+                theta_max: TAU,
+            },
+        )
     }
 
     pub fn new_cylinder(position: Vec3, radius: f32, height: f32, axis: Vec3) -> Self {
@@ -124,12 +446,15 @@ impl Surface {
             axis,
             axis.any_orthonormal_vector(),
         );
-        Surface {
+        Surface::from_collider(
             transform,
-            collider: Collider::Cylinder,
-            emissivity: 1.0,
-            material: Material::Diffuse,
-        }
+            Collider::Cylinder {
+                theta_min: 0.0,
+                theta_max: TAU,
+                z_min: -0.5,
+                z_max: 0.5,
+            },
+        )
     }
 
     pub fn new_rectangle(
@@ -150,12 +475,7 @@ impl Surface {
             axis_x,
         );
 
-        Surface {
-            transform,
-            collider: Collider::Rectangle,
-            emissivity: 1.0,
-            material: Material::Diffuse,
-        }
+        Surface::from_collider(transform, Collider::Rectangle)
     }
 
     pub fn new_cone(position: Vec3, radius: f32, height: f32, axis: Vec3) -> Self {
@@ -169,26 +489,442 @@ impl Surface {
             axis,
             axis.any_orthonormal_vector(),
         );
-        Surface {
+        Surface::from_collider(
             transform,
-            collider: Collider::Cone,
-            emissivity: 1.0,
-            material: Material::Diffuse,
+            Collider::Cone {
+                theta_min: 0.0,
+                theta_max: TAU,
+                z_max: 1.0,
+            },
+        )
+    }
+
+    pub fn new_triangle(a: Vec3, b: Vec3, c: Vec3) -> Self {
+        let axis_x = b - a;
+        let axis_y = c - a;
+        let normal = axis_x.cross(axis_y);
+        assert!(normal.length_squared() > 0.0, "Triangle is degenerate");
+
+        // Counter-clockwise winding (a -> b -> c) faces the normal
+        let transform = Transform::from_basis(a, axis_x, axis_y, normal.normalize());
+        Surface::from_collider(transform, Collider::Triangle)
+    }
+
+    // Flat ring between the inner and outer radius
+    pub fn new_annulus(position: Vec3, inner_radius: f32, outer_radius: f32, normal: Vec3) -> Self {
+        assert!(
+            inner_radius >= 0.0 && inner_radius < outer_radius,
+            "Inner radius must be smaller than the outer radius"
+        );
+
+        let transform = Transform::new(
+            position,
+            Vec3::ONE * outer_radius,
+            normal,
+            normal.any_orthonormal_vector(),
+        );
+        Surface::from_collider(
+            transform,
+            Collider::Annulus {
+                inner_radius: inner_radius / outer_radius,
+                theta_min: 0.0,
+                theta_max: TAU,
+            },
+        )
+    }
+
+    // Parallelogram with a corner at origin and sides along edge_1 and edge_2
+    pub fn new_parallelogram(origin: Vec3, edge_1: Vec3, edge_2: Vec3) -> Self {
+        let normal = edge_1.cross(edge_2);
+        assert!(normal.length_squared() > 0.0, "Parallelogram is degenerate");
+
+        let center = origin + 0.5 * (edge_1 + edge_2);
+        let transform = Transform::from_basis(center, edge_1, edge_2, normal.normalize());
+        Surface::from_collider(transform, Collider::Rectangle)
+    }
+
+    pub fn optics(&self, side: Side) -> &Optics {
+        match side {
+            Side::Front => &self.front,
+            Side::Back => &self.back,
+        }
+    }
+
+    // Picks the side a ray is emitted from
+    pub fn emission_side(&self, rng: &mut impl Rng) -> Side {
+        self.pick_emission_side(self.front_emission_fraction(None), rng)
+    }
+
+    // Picks the side a ray is emitted from, given the chance of it being the front when both are
+    // active
+    pub(crate) fn pick_emission_side(&self, front_fraction: f32, rng: &mut impl Rng) -> Side {
+        match self.active_sides {
+            ActiveSides::FrontEmitting | ActiveSides::Front => Side::Front,
+            ActiveSides::Back => Side::Back,
+            ActiveSides::Both => {
+                if rng.gen_range(0.0..1.0f32) < front_fraction {
+                    Side::Front
+                } else {
+                    Side::Back
+                }
+            }
+        }
+    }
+
+    // Share of the surface's emission that leaves from the front, in a band or overall
+    pub(crate) fn front_emission_fraction(&self, band: Option<usize>) -> f32 {
+        match self.active_sides {
+            ActiveSides::FrontEmitting | ActiveSides::Front => 1.0,
+            ActiveSides::Back => 0.0,
+            ActiveSides::Both => {
+                let emissivity = |side| match band {
+                    Some(band) => self.band_emissivity(side, band),
+                    None => self.optics(side).emissivity,
+                };
+                let total = emissivity(Side::Front) + emissivity(Side::Back);
+                if total > 0.0 {
+                    emissivity(Side::Front) / total
+                } else {
+                    0.5
+                }
+            }
+        }
+    }
+
+    // Emissivity times area, summed over the active sides, in a band or overall
+    pub fn emitting_area(&self, band: Option<usize>) -> f32 {
+        let emissivity = |side| match band {
+            Some(band) => self.band_emissivity(side, band),
+            None => self.optics(side).emissivity,
+        };
+        let emissivity = match self.active_sides {
+            ActiveSides::FrontEmitting | ActiveSides::Front => emissivity(Side::Front),
+            ActiveSides::Back => emissivity(Side::Back),
+            ActiveSides::Both => emissivity(Side::Front) + emissivity(Side::Back),
+        };
+        emissivity * self.area()
+    }
+
+    // Hemispherical emissivity of a side in a spectral band
+    pub fn band_emissivity(&self, side: Side, band: usize) -> f32 {
+        self.band_override(band)
+            .map_or(self.optics(side).emissivity, |own| own.emissivity)
+    }
+
+    // Absorptivity of a side in a spectral band, for energy arriving along a direction with the
+    // given cosine to the normal
+    pub fn band_absorptivity(&self, side: Side, band: usize, cos_theta: f32) -> f32 {
+        match self.band_override(band) {
+            Some(own) => own
+                .directional
+                .as_ref()
+                .map_or(own.emissivity, |directional| directional.at(cos_theta)),
+            None => self.optics(side).absorptivity(cos_theta),
+        }
+    }
+
+    // Directional emissivity of a side in a spectral band, if it is not the same in every
+    // direction
+    pub(crate) fn band_directional(
+        &self,
+        side: Side,
+        band: usize,
+    ) -> Option<&DirectionalEmissivity> {
+        match self.band_override(band) {
+            Some(own) => own.directional.as_ref(),
+            None => self.optics(side).directional.as_ref(),
         }
     }
 
+    // Emissivity the surface has of its own in a band, rather than that of its optics
+    fn band_override(&self, band: usize) -> Option<&BandEmissivity> {
+        self.band_emissivities.get(band)?.as_ref()
+    }
+
+    // Number of bands the surface has emissivities for, up to the last one set
+    pub(crate) fn num_band_emissivities(&self) -> usize {
+        self.band_emissivities.len()
+    }
+
+    // Sets the emissivity of both sides, the same in every direction
     pub fn to_gray_body(mut self, emissivity: f32) -> Self {
-        self.emissivity = emissivity;
+        for optics in [&mut self.front, &mut self.back] {
+            optics.emissivity = emissivity;
+            optics.directional = None;
+        }
+        self.validate_optics();
+        self
+    }
+
+    // Makes the emissivity of both sides depend on the angle from the normal
+    pub fn set_directional_emissivity(mut self, model: EmissivityModel) -> Self {
+        let directional = DirectionalEmissivity::new(model);
+        for optics in [&mut self.front, &mut self.back] {
+            optics.emissivity = directional.hemispherical();
+            optics.directional = Some(directional.clone());
+        }
+        self.validate_optics();
         self
     }
 
     pub fn set_diffuse(mut self) -> Self {
-        self.material = Material::Diffuse;
+        self.front.material = Material::Diffuse;
+        self.back.material = Material::Diffuse;
         self
     }
 
     pub fn set_specular(mut self) -> Self {
-        self.material = Material::Specular;
+        self.front.material = Material::Specular;
+        self.back.material = Material::Specular;
+        self
+    }
+
+    // Reflects a fraction of rays like a mirror and the rest diffusely, on both sides
+    pub fn set_mixed(mut self, specularity: f32) -> Self {
+        let material = Material::Mixed { specularity };
+        self.front.material = material.clone();
+        self.back.material = material;
+        self.validate_optics();
+        self
+    }
+
+    // Transmits a fraction of rays on both sides, refracting them if there is a refractive index.
+    // Rays passing through from the back see the inverse of the refractive index. Transmitted rays
+    // can come back to the surface from behind, so both sides are made active.
+    pub fn set_transmissive(mut self, transmissivity: f32, refractive_index: Option<f32>) -> Self {
+        let material = Material::Transmissive {
+            transmissivity,
+            refractive_index,
+        };
+        self.front.material = material.clone();
+        self.back.material = material;
+        self.active_sides = ActiveSides::Both;
+        self.validate_optics();
+        self
+    }
+
+    // Reflects according to a tabulated BRDF on both sides, absorbing and emitting what it does not
+    // reflect
+    pub fn set_brdf(mut self, brdf: TabulatedBrdf) -> Self {
+        let brdf = Arc::new(brdf);
+        for optics in [&mut self.front, &mut self.back] {
+            optics.emissivity = brdf.emissivity().hemispherical();
+            optics.directional = Some(brdf.emissivity().clone());
+            optics.material = Material::Brdf(brdf.clone());
+        }
+        self.validate_optics();
+        self
+    }
+
+    // Gives both sides a gray emissivity of their own in a spectral band of the scene, e.g. the
+    // solar absorptivity. The band absorbs and emits the same in every direction, even where the
+    // sides' optics have a directional emissivity. Bands without one use the emissivity of each
+    // side's optics.
+    //
+    // Only emissivity can differ between bands: every band shares the materials, so a side
+    // reflects each band in the same directions. BRDF surfaces cannot have band emissivities,
+    // since their tables fix what they absorb.
+    pub fn set_band_emissivity(self, band: usize, emissivity: f32) -> Result<Self, BandError> {
+        self.set_band(
+            band,
+            BandEmissivity {
+                emissivity,
+                directional: None,
+            },
+        )
+    }
+
+    // Gives both sides an emissivity of their own in a spectral band that depends on the angle
+    // from the normal, with the same limits as set_band_emissivity
+    pub fn set_band_emissivity_model(
+        self,
+        band: usize,
+        model: EmissivityModel,
+    ) -> Result<Self, BandError> {
+        let directional = DirectionalEmissivity::new(model);
+        self.set_band(
+            band,
+            BandEmissivity {
+                emissivity: directional.hemispherical(),
+                directional: Some(directional),
+            },
+        )
+    }
+
+    fn set_band(mut self, band: usize, emissivity: BandEmissivity) -> Result<Self, BandError> {
+        if self.has_brdf() {
+            return Err(BandError::Brdf { band });
+        }
+
+        if self.band_emissivities.len() <= band {
+            self.band_emissivities.resize(band + 1, None);
+        }
+        self.band_emissivities[band] = Some(emissivity);
+        self.validate_optics();
+        Ok(self)
+    }
+
+    fn has_brdf(&self) -> bool {
+        [&self.front, &self.back]
+            .iter()
+            .any(|optics| matches!(optics.material, Material::Brdf(_)))
+    }
+
+    fn validate_optics(&self) {
+        self.front.validate();
+        self.back.validate();
+
+        let is_transmissive = [&self.front, &self.back]
+            .iter()
+            .any(|optics| matches!(optics.material, Material::Transmissive { .. }));
+        assert!(
+            !is_transmissive || self.active_sides == ActiveSides::Both,
+            "Transmissive surfaces must have both sides active"
+        );
+
+        // Each band must make sense with the materials it shares
+        assert!(
+            !self.has_brdf() || self.band_emissivities.iter().all(Option::is_none),
+            "BRDF surfaces cannot have band emissivities"
+        );
+        for band in self.band_emissivities.iter().flatten() {
+            for optics in [&self.front, &self.back] {
+                Optics {
+                    emissivity: band.emissivity,
+                    material: optics.material.clone(),
+                    directional: band.directional.clone(),
+                }
+                .validate();
+            }
+        }
+    }
+
+    pub fn set_active_sides(mut self, active_sides: ActiveSides) -> Self {
+        self.active_sides = active_sides;
+        self.validate_optics();
+        self
+    }
+
+    // A side with a BRDF material takes its directional emissivity from the table, and emissivity
+    // must be the table's hemispherical value. A transmissive side makes both sides active.
+    pub fn set_side_optics(mut self, side: Side, emissivity: f32, material: Material) -> Self {
+        let directional = match &material {
+            Material::Brdf(brdf) => Some(brdf.emissivity().clone()),
+            _ => None,
+        };
+        if let Material::Transmissive { .. } = material {
+            self.active_sides = ActiveSides::Both;
+        }
+        let optics = Optics {
+            emissivity,
+            material,
+            directional,
+        };
+
+        match side {
+            Side::Front => self.front = optics,
+            Side::Back => self.back = optics,
+        }
+        self.validate_optics();
+        self
+    }
+
+    // Splits the surface into a grid of sub-regions that absorbed energy is also tallied over.
+    // The bin coordinates are u x v on rectangles and triangles, r x theta on disks and annuli, and
+    // theta x z on cylinders, cones, and spheres, each spaced evenly over the surface's limits.
+    pub fn set_bins(mut self, u_bins: usize, v_bins: usize) -> Self {
+        assert!(u_bins > 0 && v_bins > 0, "Surfaces need at least one bin");
+        self.bins = (u_bins, v_bins);
+        self
+    }
+
+    // Makes the surface face the opposite way, e.g. towards the axis for the inside of a pipe
+    pub fn flip_normals(mut self) -> Self {
+        self.flipped = !self.flipped;
+        self
+    }
+
+    // Limits a disk, annulus, cylinder, or cone to angles from theta_min to theta_max (radians), measured
+    // counter-clockwise about its axis starting from reference_direction
+    pub fn set_sweep(mut self, theta_min: f32, theta_max: f32, reference_direction: Vec3) -> Self {
+        assert!(
+            theta_max > theta_min && theta_max - theta_min <= TAU,
+            "Sweep must be positive and at most a full turn"
+        );
+
+        let local_reference = self.transform.vec_world_to_local(reference_direction);
+        assert!(
+            local_reference.x != 0.0 || local_reference.y != 0.0,
+            "Reference direction must not be parallel to the axis"
+        );
+        let offset = local_reference.y.atan2(local_reference.x);
+
+        match &mut self.collider {
+            Collider::Disk {
+                theta_min: min,
+                theta_max: max,
+            }
+            | Collider::Cylinder {
+                theta_min: min,
+                theta_max: max,
+                ..
+            }
+            | Collider::Cone {
+                theta_min: min,
+                theta_max: max,
+                ..
+            }
+            | Collider::Annulus {
+                theta_min: min,
+                theta_max: max,
+                ..
+            } => {
+                *min = theta_min + offset;
+                *max = theta_max + offset;
+            }
+            _ => panic!("Only disks, annuli, cylinders, and cones can be swept"),
+        }
+        self
+    }
+
+    // Limits a cylinder to the section from z_min to z_max along its axis, measured from its center
+    pub fn set_z_limits(mut self, z_min: f32, z_max: f32) -> Self {
+        let height = self.transform.m.z_axis.truncate().length();
+
+        let Collider::Cylinder {
+            z_min: min,
+            z_max: max,
+            ..
+        } = &mut self.collider
+        else {
+            panic!("Only cylinders have z limits");
+        };
+
+        assert!(z_min < z_max, "Limits must not be empty");
+        assert!(
+            z_min >= -0.5 * height && z_max <= 0.5 * height,
+            "Limits must lie within the height of the cylinder"
+        );
+
+        *min = z_min / height;
+        *max = z_max / height;
+        self
+    }
+
+    // Cuts a cone off at this height above its base, leaving a frustum
+    pub fn set_truncation_height(mut self, truncation_height: f32) -> Self {
+        let height = self.transform.m.z_axis.truncate().length();
+
+        let Collider::Cone { z_max, .. } = &mut self.collider else {
+            panic!("Only cones can be truncated");
+        };
+
+        assert!(
+            truncation_height > 0.0 && truncation_height <= height,
+            "Truncation height must lie between the base and the tip"
+        );
+
+        *z_max = truncation_height / height;
         self
     }
 }
@@ -196,22 +932,195 @@ impl Surface {
 // Not sure collider is the right phrase here
 impl Collider {
     fn intersect(&self, ray: &Ray) -> Option<Hit> {
-        match self {
+        match *self {
             Collider::Sphere => sphere::intersect(ray),
-            Collider::Disk => disk::intersect(ray),
-            Collider::Cylinder => cylinder::intersect(ray),
+            Collider::Disk {
+                theta_min,
+                theta_max,
+            } => disk::intersect(ray, theta_min, theta_max),
+            Collider::Cylinder {
+                theta_min,
+                theta_max,
+                z_min,
+                z_max,
+            } => cylinder::intersect(ray, theta_min, theta_max, z_min, z_max),
             Collider::Rectangle => rectangle::intersect(ray),
-            Collider::Cone => cone::intersect(ray),
+            Collider::Cone {
+                theta_min,
+                theta_max,
+                z_max,
+            } => cone::intersect(ray, theta_min, theta_max, z_max),
+            Collider::Triangle => triangle::intersect(ray),
+            Collider::Annulus {
+                inner_radius,
+                theta_min,
+                theta_max,
+            } => annulus::intersect(ray, inner_radius, theta_min, theta_max),
+        }
+    }
+
+    fn area(&self, m: Mat3) -> f32 {
+        match *self {
+            Collider::Sphere => sphere::area(m),
+            Collider::Disk {
+                theta_min,
+                theta_max,
+            } => disk::area(m, theta_min, theta_max),
+            Collider::Cylinder {
+                theta_min,
+                theta_max,
+                z_min,
+                z_max,
+            } => cylinder::area(m, theta_min, theta_max, z_min, z_max),
+            Collider::Rectangle => rectangle::area(m),
+            Collider::Cone {
+                theta_min,
+                theta_max,
+                z_max,
+            } => cone::area(m, theta_min, theta_max, z_max),
+            Collider::Triangle => triangle::area(m),
+            Collider::Annulus {
+                inner_radius,
+                theta_min,
+                theta_max,
+            } => annulus::area(m, inner_radius, theta_min, theta_max),
+        }
+    }
+
+    // Partial sweeps keep the bounds of the full shape
+    fn local_bounds(&self) -> Aabb {
+        let (min, max) = match *self {
+            Collider::Sphere => (Vec3::NEG_ONE, Vec3::ONE),
+            Collider::Disk { .. } | Collider::Annulus { .. } => {
+                (Vec3::new(-1., -1., 0.), Vec3::new(1., 1., 0.))
+            }
+            Collider::Cylinder { z_min, z_max, .. } => {
+                (Vec3::new(-1., -1., z_min), Vec3::new(1., 1., z_max))
+            }
+            Collider::Rectangle => (Vec3::new(-0.5, -0.5, 0.), Vec3::new(0.5, 0.5, 0.)),
+            Collider::Cone { z_max, .. } => (Vec3::new(-1., -1., 0.), Vec3::new(1., 1., z_max)),
+            Collider::Triangle => (Vec3::ZERO, Vec3::new(1., 1., 0.)),
+        };
+        Aabb { min, max }
+    }
+
+    // Position of a local point within the surface as fractions from 0 to 1 along each bin coordinate
+    fn bin_coordinates(&self, point: Vec3) -> (f32, f32) {
+        match *self {
+            Collider::Sphere => sphere::bin_coordinates(point),
+            Collider::Disk {
+                theta_min,
+                theta_max,
+            } => disk::bin_coordinates(point, theta_min, theta_max),
+            Collider::Cylinder {
+                theta_min,
+                theta_max,
+                z_min,
+                z_max,
+            } => cylinder::bin_coordinates(point, theta_min, theta_max, z_min, z_max),
+            Collider::Rectangle => rectangle::bin_coordinates(point),
+            Collider::Cone {
+                theta_min,
+                theta_max,
+                z_max,
+            } => cone::bin_coordinates(point, theta_min, theta_max, z_max),
+            Collider::Triangle => triangle::bin_coordinates(point),
+            Collider::Annulus {
+                inner_radius,
+                theta_min,
+                theta_max,
+            } => annulus::bin_coordinates(point, inner_radius, theta_min, theta_max),
+        }
+    }
+
+    // World space area of the part of the surface between the given bin coordinates
+    fn bin_area(&self, m: Mat3, u: Range<f32>, v: Range<f32>) -> f32 {
+        match *self {
+            Collider::Sphere => sphere::bin_area(m, u, v),
+            Collider::Disk {
+                theta_min,
+                theta_max,
+            } => disk::bin_area(m, theta_min, theta_max, u, v),
+            Collider::Cylinder {
+                theta_min,
+                theta_max,
+                z_min,
+                z_max,
+            } => cylinder::bin_area(m, theta_min, theta_max, z_min, z_max, u, v),
+            Collider::Rectangle => rectangle::bin_area(m, u, v),
+            Collider::Cone {
+                theta_min,
+                theta_max,
+                z_max,
+            } => cone::bin_area(m, theta_min, theta_max, z_max, u, v),
+            Collider::Triangle => triangle::bin_area(m, u, v),
+            Collider::Annulus {
+                inner_radius,
+                theta_min,
+                theta_max,
+            } => annulus::bin_area(m, inner_radius, theta_min, theta_max, u, v),
         }
     }
 
-    fn sample(&self) -> SurfaceSample {
-        match self {
-            Collider::Sphere => sphere::sample(),
-            Collider::Disk => disk::sample(),
-            Collider::Cylinder => cylinder::sample(),
-            Collider::Rectangle => rectangle::sample(),
-            Collider::Cone => cone::sample(),
+    fn sample(&self, rng: &mut impl Rng) -> SurfaceSample {
+        match *self {
+            Collider::Sphere => sphere::sample(rng),
+            Collider::Disk {
+                theta_min,
+                theta_max,
+            } => disk::sample(rng, theta_min, theta_max),
+            Collider::Cylinder {
+                theta_min,
+                theta_max,
+                z_min,
+                z_max,
+            } => cylinder::sample(rng, theta_min, theta_max, z_min, z_max),
+            Collider::Rectangle => rectangle::sample(rng),
+            Collider::Cone {
+                theta_min,
+                theta_max,
+                z_max,
+            } => cone::sample(rng, theta_min, theta_max, z_max),
+            Collider::Triangle => triangle::sample(rng),
+            Collider::Annulus {
+                inner_radius,
+                theta_min,
+                theta_max,
+            } => annulus::sample(rng, inner_radius, theta_min, theta_max),
         }
     }
 }
+
+// Whether the angle of a local point about the z axis lies within the sweep
+fn in_sweep(point: Vec3, theta_min: f32, theta_max: f32) -> bool {
+    let sweep = theta_max - theta_min;
+    sweep >= TAU || (point.y.atan2(point.x) - theta_min).rem_euclid(TAU) <= sweep
+}
+
+// Fraction of the way through the sweep of a local point's angle about the z axis
+fn sweep_fraction(point: Vec3, theta_min: f32, theta_max: f32) -> f32 {
+    let sweep = theta_max - theta_min;
+    (point.y.atan2(point.x) - theta_min).rem_euclid(TAU) / sweep
+}
+
+// Angles bounding a fraction of the sweep
+fn sweep_range(theta_min: f32, theta_max: f32, fraction: Range<f32>) -> (f32, f32) {
+    let sweep = theta_max - theta_min;
+    (
+        theta_min + fraction.start * sweep,
+        theta_min + fraction.end * sweep,
+    )
+}
+
+// Midpoint rule over an angle range, which converges very quickly for the smooth periodic
+// integrands of surfaces of revolution
+fn integrate_angle(theta_min: f32, theta_max: f32, f: impl Fn(f32) -> f32) -> f32 {
+    const STEPS: usize = 1024;
+
+    let step = (theta_max - theta_min) / STEPS as f32;
+    let sum: f64 = (0..STEPS)
+        .map(|i| f(theta_min + (i as f32 + 0.5) * step) as f64)
+        .sum();
+
+    (sum * step as f64) as f32
+}
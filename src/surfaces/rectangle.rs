@@ -1,11 +1,11 @@
 use crate::{Hit, Normal, Point, Ray, SurfaceSample};
 
-use glam::Vec3;
-use rand::{thread_rng, Rng};
+use std::ops::Range;
 
-pub fn sample() -> SurfaceSample {
-    let mut rng = thread_rng();
+use glam::{Mat3, Vec3};
+use rand::Rng;
 
+pub fn sample(rng: &mut impl Rng) -> SurfaceSample {
     let x = rng.gen_range(-0.5..0.5);
     let y = rng.gen_range(-0.5..0.5);
 
@@ -42,3 +42,17 @@ pub fn intersect(ray: &Ray) -> Option<Hit> {
         None
     }
 }
+
+// Unit square stretched by the transform of its plane
+pub fn area(m: Mat3) -> f32 {
+    m.x_axis.cross(m.y_axis).length()
+}
+
+// Fractions across the local x and y extents
+pub fn bin_coordinates(point: Vec3) -> (f32, f32) {
+    (point.x + 0.5, point.y + 0.5)
+}
+
+pub fn bin_area(m: Mat3, u: Range<f32>, v: Range<f32>) -> f32 {
+    area(m) * (u.end - u.start) * (v.end - v.start)
+}
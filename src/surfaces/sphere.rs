@@ -1,10 +1,11 @@
 use crate::{Hit, Normal, Point, Ray, SurfaceSample};
 
-use glam::f32::Vec3;
-use rand::{thread_rng, Rng};
+use std::ops::Range;
 
-pub fn sample() -> SurfaceSample {
-    let mut rng = thread_rng();
+use glam::f32::{Mat3, Vec3};
+use rand::Rng;
+
+pub fn sample(rng: &mut impl Rng) -> SurfaceSample {
     let phi = rng.gen_range(0.0..std::f32::consts::TAU);
     let u = rng.gen_range(-1.0..1.0);
 
@@ -55,3 +56,31 @@ pub fn intersect(ray: &Ray) -> Option<Hit> {
 
     Some(hit)
 }
+
+// The unit sphere only keeps a closed form area when it is scaled evenly into another sphere
+pub fn area(m: Mat3) -> f32 {
+    let radius_squared = m.x_axis.length_squared();
+
+    let tolerance = 1e-4 * radius_squared;
+    let is_sphere = (m.y_axis.length_squared() - radius_squared).abs() <= tolerance
+        && (m.z_axis.length_squared() - radius_squared).abs() <= tolerance
+        && m.x_axis.dot(m.y_axis).abs() <= tolerance
+        && m.y_axis.dot(m.z_axis).abs() <= tolerance
+        && m.z_axis.dot(m.x_axis).abs() <= tolerance;
+    assert!(
+        is_sphere,
+        "Area of a sphere with anisotropic scale is not supported"
+    );
+
+    4.0 * std::f32::consts::PI * radius_squared
+}
+
+// Fractions around the local z axis and up from the bottom, so equal bins have equal areas
+pub fn bin_coordinates(point: Vec3) -> (f32, f32) {
+    let u = point.y.atan2(point.x).rem_euclid(std::f32::consts::TAU) / std::f32::consts::TAU;
+    (u, 0.5 * (point.z + 1.0))
+}
+
+pub fn bin_area(m: Mat3, u: Range<f32>, v: Range<f32>) -> f32 {
+    area(m) * (u.end - u.start) * (v.end - v.start)
+}
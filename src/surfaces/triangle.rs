@@ -0,0 +1,69 @@
+use crate::{Hit, Normal, Point, Ray, SurfaceSample};
+
+use std::ops::Range;
+
+use glam::{Mat3, Vec3};
+use rand::Rng;
+
+// Local triangle has vertices at (0, 0, 0), (1, 0, 0), and (0, 1, 0)
+pub fn sample(rng: &mut impl Rng) -> SurfaceSample {
+    let mut x = rng.gen_range(0.0..1.0);
+    let mut y = rng.gen_range(0.0..1.0);
+
+    // Fold samples from the far half of the unit square back onto the triangle
+    if x + y > 1.0 {
+        x = 1.0 - x;
+        y = 1.0 - y;
+    }
+
+    SurfaceSample {
+        position: Point(Vec3 { x, y, z: 0.0 }),
+        normal: Normal::new(Vec3::Z),
+    }
+}
+
+pub fn intersect(ray: &Ray) -> Option<Hit> {
+    // Ensure ray intersects plane
+    if ray.direction.z == 0.0 {
+        return None;
+    }
+
+    // Calculate the intersection point by solving for t in the equation:
+    // origin + t * direction = intersection
+    // where intersection is a point on the xy plane (z = 0).
+    let t = -ray.origin.0.z / ray.direction.z;
+
+    if t < 0.0001 {
+        return None;
+    }
+
+    let pos = ray.at(t);
+
+    if pos.x > 0.0 && pos.y > 0.0 && pos.x + pos.y < 1.0 {
+        Some(Hit {
+            normal: Normal::new(Vec3::Z),
+            position: Point(pos),
+            t,
+        })
+    } else {
+        None
+    }
+}
+
+// Right triangle with unit legs stretched by the transform of its plane
+pub fn area(m: Mat3) -> f32 {
+    0.5 * m.x_axis.cross(m.y_axis).length()
+}
+
+// The triangle is treated as a unit square with its top edge collapsed onto the vertex at (0, 1, 0),
+// so u runs across the triangle and v from the x axis to that vertex
+pub fn bin_coordinates(point: Vec3) -> (f32, f32) {
+    let width = 1.0 - point.y;
+    let u = if width > 0.0 { point.x / width } else { 0.0 };
+    (u, point.y)
+}
+
+pub fn bin_area(m: Mat3, u: Range<f32>, v: Range<f32>) -> f32 {
+    let strip = (1.0 - v.start).powi(2) - (1.0 - v.end).powi(2);
+    area(m) * (u.end - u.start) * strip
+}
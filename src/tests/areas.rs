@@ -0,0 +1,166 @@
+#![cfg(test)]
+
+use crate::{surfaces::Surface, transform::Transform, Point, Ray};
+
+use glam::Vec3;
+use rand::{rngs::StdRng, SeedableRng};
+use std::f32::consts::PI;
+
+fn assert_close(area: f32, expected: f32) {
+    assert!((area / expected - 1.).abs() < 1e-5, "{area} vs {expected}");
+}
+
+#[test]
+fn areas_match_closed_forms() {
+    let axis = Vec3::new(1., 2., -0.5).normalize();
+
+    assert_close(Surface::new_sphere(Vec3::X, 2.).area(), 16. * PI);
+    assert_close(Surface::new_disk(Vec3::Y, 1.5, axis).area(), 2.25 * PI);
+    assert_close(
+        Surface::new_rectangle(Vec3::Z, 2., 3., axis, axis.any_orthonormal_vector()).area(),
+        6.,
+    );
+    assert_close(
+        Surface::new_cylinder(Vec3::ZERO, 0.5, 3., axis).area(),
+        3. * PI,
+    );
+    assert_close(
+        Surface::new_cone(Vec3::ZERO, 3., 4., axis).area(),
+        PI * 3. * 5.,
+    );
+    assert_close(
+        Surface::new_triangle(Vec3::ZERO, Vec3::new(3., 0., 0.), Vec3::new(1., 2., 5.)).area(),
+        0.5 * Vec3::new(3., 0., 0.).cross(Vec3::new(1., 2., 5.)).length(),
+    );
+}
+
+#[test]
+fn areas_with_shear_and_non_uniform_scale() {
+    // Parallelogram area is the length of the cross product of its edges
+    let (edge_1, edge_2) = (Vec3::new(2., 0., 0.), Vec3::new(1., 3., 0.));
+    assert_close(
+        Surface::new_parallelogram(Vec3::ZERO, edge_1, edge_2).area(),
+        6.,
+    );
+
+    // Elliptical cylinder, compared against Ramanujan's approximation of the ellipse perimeter
+    let mut cylinder = Surface::new_cylinder(Vec3::ZERO, 1., 2., Vec3::Z);
+    cylinder.transform = Transform::new(Vec3::ZERO, Vec3::new(3., 1., 2.), Vec3::Z, Vec3::X);
+    let (a, b) = (3f32, 1f32);
+    let h = ((a - b) / (a + b)).powi(2);
+    let perimeter = PI * (a + b) * (1. + 3. * h / (10. + (4. - 3. * h).sqrt()));
+    assert!((cylinder.area() / (2. * perimeter) - 1.).abs() < 1e-3);
+
+    // Sheared side wall, compared against thin flat panels between points on its base
+    let axis_z = Vec3::new(1., 1., 2.);
+    cylinder.transform = Transform::from_basis(Vec3::ZERO, Vec3::X, 2. * Vec3::Y, axis_z);
+    let base = |i: usize| {
+        let theta = i as f32 / 2000. * 2. * PI;
+        Vec3::new(theta.cos(), 2. * theta.sin(), 0.)
+    };
+    let panels: f32 = (0..2000)
+        .map(|i| (base(i + 1) - base(i)).cross(axis_z).length())
+        .sum();
+    assert!((cylinder.area() / panels - 1.).abs() < 1e-4);
+}
+
+#[test]
+fn partial_areas_match_closed_forms() {
+    assert_close(
+        Surface::new_disk(Vec3::ZERO, 2., Vec3::Z)
+            .set_sweep(0.5, 2., Vec3::X)
+            .area(),
+        0.5 * 1.5 * 4.,
+    );
+    assert_close(
+        Surface::new_cylinder(Vec3::ZERO, 2., 4., Vec3::X)
+            .set_sweep(0., PI, Vec3::Y)
+            .set_z_limits(-1., 0.5)
+            .area(),
+        PI * 2. * 1.5,
+    );
+
+    // Frustum slant height times the mean circumference
+    let (r_base, r_top, height) = (3f32, 1f32, 2f32);
+    let slant = (height * height + (r_base - r_top) * (r_base - r_top)).sqrt();
+    assert_close(
+        Surface::new_cone(Vec3::ZERO, r_base, 3., Vec3::Y)
+            .set_truncation_height(height)
+            .area(),
+        PI * (r_base + r_top) * slant,
+    );
+}
+
+#[test]
+fn annulus_area_and_sampling() {
+    let annulus = Surface::new_annulus(Vec3::ZERO, 1., 2., Vec3::Z);
+    assert_close(annulus.area(), PI * (4. - 1.));
+    assert_close(
+        annulus.clone().set_sweep(0., 1., Vec3::X).area(),
+        0.5 * (4. - 1.),
+    );
+
+    // Uniform over the area, so the fraction inside radius 1.5 matches its share of the area
+    let mut rng = StdRng::seed_from_u64(2);
+    let inside = (0..100_000)
+        .map(|_| annulus.sample(&mut rng).position.0.length())
+        .inspect(|&r| assert!((1. - 1e-5..=2. + 1e-5).contains(&r)))
+        .filter(|&r| r < 1.5)
+        .count();
+    assert!((inside as f32 / 100_000. - 1.25 / 3.).abs() < 0.005);
+}
+
+#[test]
+fn partial_samples_and_hits_respect_limits() {
+    let mut rng = StdRng::seed_from_u64(1);
+
+    // Upper half of the side wall, on the +y side of the axis
+    let cylinder = Surface::new_cylinder(Vec3::ZERO, 1., 2., Vec3::Z)
+        .set_sweep(0., PI, Vec3::X)
+        .set_z_limits(0., 1.);
+    let frustum = Surface::new_cone(Vec3::ZERO, 1., 2., Vec3::Z)
+        .set_sweep(0., PI, Vec3::X)
+        .set_truncation_height(1.);
+
+    for surface in [&cylinder, &frustum] {
+        for _ in 0..1000 {
+            let position = surface.sample(&mut rng).position.0;
+            assert!(position.y >= -1e-6);
+            assert!(position.z >= -1e-6 && position.z <= 1. + 1e-6);
+        }
+    }
+
+    // Rays through the cut away parts pass straight through
+    let ray = |origin: Vec3, direction: Vec3| Ray {
+        origin: Point(origin),
+        direction,
+    };
+    assert!(cylinder.intersect(&ray(Vec3::ZERO, Vec3::NEG_Y)).is_none());
+    assert!(cylinder
+        .intersect(&ray(Vec3::new(0., 0., -0.5), Vec3::Y))
+        .is_none());
+    assert!(cylinder
+        .intersect(&ray(Vec3::new(0., 0., 0.5), Vec3::Y))
+        .is_some());
+    assert!(frustum
+        .intersect(&ray(Vec3::new(0., 0., 1.5), Vec3::X))
+        .is_none());
+    assert!(frustum
+        .intersect(&ray(Vec3::new(0., 0., 0.5), Vec3::Y))
+        .is_some());
+
+    // From outside, the near side of a full cylinder is hit first
+    let full = Surface::new_cylinder(Vec3::ZERO, 1., 2., Vec3::Z);
+    let hit = full
+        .intersect(&ray(Vec3::new(3., 0., 0.), Vec3::NEG_X))
+        .unwrap();
+    assert!((hit.t - 2.).abs() < 1e-5);
+}
+
+#[test]
+#[should_panic(expected = "anisotropic")]
+fn anisotropic_sphere_area_is_rejected() {
+    let mut sphere = Surface::new_sphere(Vec3::ZERO, 1.);
+    sphere.transform = Transform::new(Vec3::ZERO, Vec3::new(1., 2., 1.), Vec3::Z, Vec3::X);
+    sphere.area();
+}
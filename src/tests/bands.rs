@@ -0,0 +1,173 @@
+#![cfg(test)]
+
+use crate::{
+    surfaces::{ActiveSides, EmissivityModel, Material, Side, Surface},
+    Scene, SurfaceId,
+};
+
+use glam::Vec3;
+
+fn percent_error(expected: f32, measured: f32) -> f32 {
+    100. * (measured - expected) / expected
+}
+
+#[test]
+fn plates_in_each_band() {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(15));
+    scene.set_bands(&["solar", "infrared"]);
+    assert_eq!(scene.find_band("infrared"), Some(1));
+
+    // The lower plate has its own emissivity in both bands, the upper only in the solar band
+    let length = 10_000.;
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::ZERO, length, length, Vec3::Z, Vec3::X)
+            .to_gray_body(0.5)
+            .set_band_emissivity(0, 0.2)
+            .unwrap()
+            .set_band_emissivity(1, 0.9)
+            .unwrap(),
+    );
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::Z, length, length, Vec3::NEG_Z, Vec3::NEG_X)
+            .to_gray_body(0.4)
+            .set_band_emissivity(0, 0.7)
+            .unwrap(),
+    );
+
+    let bands = scene.view_factors_for_surface_in_bands(SurfaceId(0), 200_000);
+    for (band, (e1, e2)) in [(0.2, 0.7), (0.9, 0.4)].into_iter().enumerate() {
+        let expected = e2 / (e1 + e2 - e1 * e2);
+        assert!(percent_error(expected, bands[band][1].mean).abs() < 1.);
+    }
+}
+
+#[test]
+fn emission_sides_in_each_band() {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(16));
+    scene.set_bands(&["solar", "infrared"]);
+
+    // Small two-sided panel between black plates, emitting mostly from the back unless a band
+    // gives both sides the same emissivity
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::ZERO, 0.1, 0.1, Vec3::Z, Vec3::X)
+            .set_active_sides(ActiveSides::Both)
+            .set_side_optics(Side::Front, 0.2, Material::Diffuse)
+            .set_side_optics(Side::Back, 0.8, Material::Diffuse)
+            .set_band_emissivity(0, 0.5)
+            .unwrap(),
+    );
+    scene.add_surface(Surface::new_rectangle(
+        Vec3::Z,
+        10_000.,
+        10_000.,
+        Vec3::NEG_Z,
+        Vec3::X,
+    ));
+    scene.add_surface(Surface::new_rectangle(
+        Vec3::NEG_Z,
+        10_000.,
+        10_000.,
+        Vec3::Z,
+        Vec3::X,
+    ));
+
+    let bands = scene.view_factors_for_surface_in_bands(SurfaceId(0), 100_000);
+    assert!((bands[0][1].mean - 0.5).abs() < 0.01);
+    assert!((bands[0][2].mean - 0.5).abs() < 0.01);
+    assert!((bands[1][1].mean - 0.2).abs() < 0.01);
+    assert!((bands[1][2].mean - 0.8).abs() < 0.01);
+}
+
+// Glass disk under a pane that transmits 0.6 and a black disk above it. With bands, both have
+// an infrared emissivity of their own, and the pane absorbs 0.3 in that band.
+fn glass_under_pane(in_bands: bool) -> Scene {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(17));
+
+    let mut glass = Surface::new_disk(Vec3::ZERO, 1., Vec3::Z).set_directional_emissivity(
+        EmissivityModel::Dielectric {
+            refractive_index: 1.5,
+        },
+    );
+    let mut pane = Surface::new_rectangle(Vec3::Z, 1000., 1000., Vec3::NEG_Z, Vec3::X)
+        .to_gray_body(0.1)
+        .set_transmissive(0.6, None);
+    if in_bands {
+        scene.set_bands(&["visible", "infrared"]);
+        glass = glass.set_band_emissivity(1, 0.9).unwrap();
+        pane = pane.set_band_emissivity(1, 0.3).unwrap();
+    }
+
+    scene.add_surface(glass);
+    scene.add_surface(pane);
+    scene.add_surface(Surface::new_disk(2. * Vec3::Z, 1., Vec3::NEG_Z));
+    scene
+}
+
+#[test]
+fn shared_paths_match_separate_runs() {
+    let visible = glass_under_pane(false).view_factors_for_surface(SurfaceId(0), 500_000);
+    let bands = glass_under_pane(true).view_factors_for_surface_in_bands(SurfaceId(0), 500_000);
+
+    // The band without emissivities of its own sees the surfaces' optics
+    for (expected, measured) in visible.means().into_iter().zip(bands[0].means()) {
+        assert!(percent_error(expected, measured).abs() < 2.);
+    }
+
+    // The other band emits diffusely and the pane only reflects 0.1 of it
+    let x: f32 = 1. + (1. + 0.25) / 0.25;
+    let direct = 0.5 * (x - (x * x - 4.).sqrt());
+    let infrared = bands[1].means();
+    assert!(percent_error(0.3, infrared[1]).abs() < 1.);
+    assert!(percent_error(0.6 * direct, infrared[2]).abs() < 2.);
+}
+
+#[test]
+fn directional_emissivity_in_one_band() {
+    let dielectric = EmissivityModel::Dielectric {
+        refractive_index: 1.5,
+    };
+
+    // Emitting disk half a unit under a black disk
+    let scene = |glass: Surface| {
+        let mut scene = Scene::default();
+        scene.set_seed(Some(19));
+        scene.set_bands(&["visible", "infrared"]);
+        scene.add_surface(glass);
+        scene.add_surface(Surface::new_disk(0.5 * Vec3::Z, 1., Vec3::NEG_Z));
+        scene
+    };
+    // Gray in the visible band, and dielectric in the infrared band only
+    let glass = Surface::new_disk(Vec3::ZERO, 1., Vec3::Z).to_gray_body(0.9);
+    let bands = scene(
+        glass
+            .clone()
+            .set_band_emissivity_model(1, dielectric.clone())
+            .unwrap(),
+    )
+    .view_factors_for_surface_in_bands(SurfaceId(0), 500_000);
+
+    // Each band matches the surface with that band's emissivity throughout
+    let gray = scene(glass.clone()).view_factors_for_surface(SurfaceId(0), 500_000);
+    let directional = scene(glass.set_directional_emissivity(dielectric))
+        .view_factors_for_surface(SurfaceId(0), 500_000);
+    assert!(percent_error(gray[1].mean, bands[0][1].mean).abs() < 1.);
+    assert!(percent_error(directional[1].mean, bands[1][1].mean).abs() < 1.);
+
+    // The dielectric emits less at grazing angles, so more of its emission reaches the disk above
+    assert!(directional[1].mean > gray[1].mean * 1.02);
+}
+
+#[test]
+#[should_panic(expected = "no spectral bands")]
+fn band_computations_need_bands() {
+    let mut scene = Scene::default();
+    scene.add_surface(
+        Surface::new_disk(Vec3::ZERO, 1., Vec3::Z)
+            .set_band_emissivity(0, 0.5)
+            .unwrap(),
+    );
+    scene.view_factors_for_surface_in_bands(SurfaceId(0), 1_000);
+}
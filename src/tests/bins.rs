@@ -0,0 +1,108 @@
+#![cfg(test)]
+
+use crate::{surfaces::Surface, Point, Scene, SurfaceId};
+
+use glam::Vec3;
+use std::f32::consts::PI;
+
+#[test]
+fn bin_areas_cover_the_surface() {
+    let surfaces = [
+        Surface::new_rectangle(Vec3::ZERO, 2., 3., Vec3::Z, Vec3::X).set_bins(3, 2),
+        Surface::new_triangle(Vec3::ZERO, Vec3::X, 2. * Vec3::Y).set_bins(2, 3),
+        Surface::new_sphere(Vec3::ZERO, 2.).set_bins(4, 3),
+        Surface::new_disk(Vec3::ZERO, 2., Vec3::Z)
+            .set_sweep(0., PI, Vec3::X)
+            .set_bins(3, 2),
+        Surface::new_annulus(Vec3::ZERO, 1., 2., Vec3::Z).set_bins(2, 4),
+        Surface::new_cylinder(Vec3::ZERO, 1., 2., Vec3::Z)
+            .set_z_limits(-0.5, 1.)
+            .set_bins(4, 3),
+        Surface::new_cone(Vec3::ZERO, 1., 2., Vec3::Z)
+            .set_truncation_height(1.5)
+            .set_bins(2, 3),
+    ];
+
+    for surface in surfaces {
+        let bin_areas = surface.bin_areas();
+        assert_eq!(bin_areas.len(), surface.num_bins());
+        let total: f32 = bin_areas.iter().sum();
+        assert!((total / surface.area() - 1.).abs() < 1e-4);
+    }
+
+    // Inner ring of a disk split in half radially has a quarter of the area
+    let disk = Surface::new_disk(Vec3::ZERO, 2., Vec3::Z).set_bins(2, 1);
+    assert!((disk.bin_areas()[0] - PI).abs() < 1e-4);
+}
+
+#[test]
+fn points_map_to_bins() {
+    let rectangle = Surface::new_rectangle(Vec3::ZERO, 2., 1., Vec3::Z, Vec3::X).set_bins(4, 2);
+    assert_eq!(rectangle.bin(&Point(Vec3::new(-0.9, -0.4, 0.))), 0);
+    assert_eq!(rectangle.bin(&Point(Vec3::new(-0.9, 0.4, 0.))), 1);
+    assert_eq!(rectangle.bin(&Point(Vec3::new(0.9, 0.4, 0.))), 7);
+
+    // Radial bins first, then quarters of the turn counter-clockwise from the local x axis
+    let disk = Surface::new_disk(Vec3::ZERO, 1., Vec3::Z)
+        .set_sweep(0., 2. * PI, Vec3::X)
+        .set_bins(2, 4);
+    assert_eq!(disk.bin(&Point(Vec3::new(0.2, 0.1, 0.))), 0);
+    assert_eq!(disk.bin(&Point(Vec3::new(-0.2, 0.1, 0.))), 1);
+    assert_eq!(disk.bin(&Point(Vec3::new(0.1, -0.8, 0.))), 7);
+
+    // Points on the edges stay in the outermost bins
+    let cylinder = Surface::new_cylinder(Vec3::ZERO, 1., 2., Vec3::Z).set_bins(1, 4);
+    assert_eq!(cylinder.bin(&Point(Vec3::new(1., 0., 1.))), 3);
+    assert_eq!(cylinder.bin(&Point(Vec3::new(1., 0., -1.))), 0);
+}
+
+// Analytical Source: http://webserver.dmt.upm.es/~isidoro/tc3/Radiation%20View%20factors.pdf
+// Coaxial parallel disks of radius r1 and r2 a unit distance apart
+fn coaxial_disks(r1: f32, r2: f32) -> f32 {
+    let x = 1. + (1. + r2 * r2) / (r1 * r1);
+    0.5 * (x - (x * x - 4. * (r2 / r1).powi(2)).sqrt())
+}
+
+#[test]
+fn rings_of_a_disk() {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(7));
+
+    scene.add_surface(Surface::new_disk(Vec3::Z, 1., Vec3::NEG_Z));
+    scene.add_surface(Surface::new_disk(Vec3::ZERO, 2., Vec3::Z).set_bins(2, 3));
+
+    let view_factors = scene.view_factors_for_surface(SurfaceId(0), 1_000_000);
+    assert!(view_factors.bins[0].is_empty());
+
+    let bins = &view_factors.bins[1];
+    let ring = |r: usize| (0..3).map(|j| bins[3 * r + j].mean).sum::<f32>();
+    let inner = coaxial_disks(1., 1.);
+    let outer = coaxial_disks(1., 2.) - inner;
+    assert!((ring(0) / inner - 1.).abs() < 0.01);
+    assert!((ring(1) / outer - 1.).abs() < 0.01);
+
+    // Bins partition the surface, so they add up to it
+    let total: f32 = bins.iter().map(|bin| bin.mean).sum();
+    assert!((total - view_factors[1].mean).abs() < 1e-5);
+}
+
+#[test]
+fn bands_of_a_cylinder() {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(8));
+
+    // Point-like sphere at the center of an open tube
+    scene.add_surface(Surface::new_sphere(Vec3::ZERO, 0.001));
+    scene.add_surface(
+        Surface::new_cylinder(Vec3::ZERO, 1., 2., Vec3::Z)
+            .flip_normals()
+            .set_bins(4, 2),
+    );
+
+    // Rays leave the center evenly in every direction, so each band gets the solid angle it spans
+    let view_factors = scene.view_factors_for_surface(SurfaceId(0), 1_000_000);
+    let expected = 0.25 * 0.5 / 2f32.sqrt();
+    for bin in &view_factors.bins[1] {
+        assert!((bin.mean / expected - 1.).abs() < 0.02);
+    }
+}
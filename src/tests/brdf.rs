@@ -0,0 +1,195 @@
+#![cfg(test)]
+
+use std::{
+    f32::consts::{FRAC_PI_6, PI},
+    sync::Arc,
+};
+
+use crate::{
+    surfaces::{BandError, BrdfError, Material, Side, Surface, TabulatedBrdf},
+    Scene,
+};
+
+use glam::Vec3;
+
+// Table over the given incidence angles and a regular grid of reflection angles and azimuths
+fn tabulate(
+    incidence_degrees: &[f32],
+    step_degrees: f32,
+    brdf: impl Fn(f32, f32, f32) -> f32,
+) -> TabulatedBrdf {
+    let grid = |max: f32| {
+        (0..=(max / step_degrees).round() as usize)
+            .map(|i| (i as f32 * step_degrees).to_radians())
+            .collect::<Vec<f32>>()
+    };
+    let incidence: Vec<f32> = incidence_degrees.iter().map(|a| a.to_radians()).collect();
+    let (reflection, azimuth) = (grid(90.), grid(180.));
+
+    let mut values = Vec::new();
+    for &theta_i in &incidence {
+        for &theta_r in &reflection {
+            for &phi in &azimuth {
+                values.push(brdf(theta_i, theta_r, phi));
+            }
+        }
+    }
+    TabulatedBrdf::new(incidence, reflection, azimuth, values).unwrap()
+}
+
+#[test]
+fn lambertian_table_matches_diffuse() {
+    let lambertian = tabulate(&[0.], 15., |_, _, _| 1. / PI);
+    assert!((lambertian.reflectance(1.) - 1.).abs() < 1e-5);
+    assert!(lambertian.emissivity().hemispherical() < 1e-5);
+
+    let diffuse = super::distribution_factors::disk_over_reflector(Material::Diffuse);
+    let tabulated =
+        super::distribution_factors::disk_over_reflector(Material::Brdf(Arc::new(lambertian)));
+    assert!((tabulated / diffuse - 1.).abs() < 0.01);
+}
+
+#[test]
+fn reflections_follow_the_table() {
+    let mut rng = rand::thread_rng();
+    let normal = Surface::new_disk(Vec3::ZERO, 1., Vec3::Z)
+        .sample(&mut rng)
+        .normal;
+    let incoming = Vec3::new(FRAC_PI_6.sin(), 0., -FRAC_PI_6.cos());
+    let mirror = normal.to_specular_ray(incoming);
+    let num_samples = 100_000;
+
+    // Diffuse reflection is uniform in sin^2, so a quarter of it lies within 30 degrees
+    let lambertian = tabulate(&[0., 90.], 10., |_, _, _| 0.5 / PI);
+    let within_30_degrees = (0..num_samples)
+        .filter(|_| lambertian.sample_direction(&normal, incoming, &mut rng).z > 0.75f32.sqrt())
+        .count();
+    assert!((within_30_degrees as f32 / num_samples as f32 - 0.25).abs() < 0.01);
+
+    // A glossy lobe around the mirror direction
+    let glossy = tabulate(
+        &[0., 15., 30., 45., 60., 75., 90.],
+        2.,
+        |theta_i, theta_r, phi| {
+            let outgoing = Vec3::new(
+                theta_r.sin() * phi.cos(),
+                theta_r.sin() * phi.sin(),
+                theta_r.cos(),
+            );
+            let reflected = Vec3::new(theta_i.sin(), 0., theta_i.cos());
+            0.1 * (-outgoing.angle_between(reflected).powi(2) / 0.01).exp()
+        },
+    );
+    let mut mean = Vec3::ZERO;
+    for _ in 0..num_samples {
+        let direction = glossy.sample_direction(&normal, incoming, &mut rng);
+        assert!(direction.z >= 0.);
+        mean += direction;
+    }
+    assert!(mean.angle_between(mirror).to_degrees() < 2.);
+}
+
+#[test]
+fn energy_conservation_is_checked() {
+    let result = TabulatedBrdf::new(
+        vec![0., 1.],
+        vec![0., PI / 2.],
+        vec![0.],
+        vec![0.3, 0.3, 0.4, 0.4],
+    );
+    match result {
+        Err(BrdfError::NotEnergyConserving {
+            incidence_angle,
+            reflectance,
+        }) => {
+            assert_eq!(incidence_angle, 1.);
+            assert!((reflectance - 0.4 * PI).abs() < 1e-5);
+        }
+        _ => panic!("table reflecting more than it receives was accepted"),
+    }
+}
+
+#[test]
+fn tables_load_from_text() {
+    let source = "\
+        # incidence reflection azimuth value
+        0 0 0 0.2
+        0 90 0 0.2
+        60 90 0 0.1 # rows may come in any order
+        60 0 0 0.1
+    ";
+    let brdf = TabulatedBrdf::parse(source).unwrap();
+    assert!((brdf.reflectance(1.) - 0.2 * PI).abs() < 1e-5);
+    assert!((brdf.reflectance(0.5) - 0.1 * PI).abs() < 1e-5);
+    assert!((brdf.reflectance(0.) - 0.1 * PI).abs() < 1e-5);
+
+    let missing_row = source.replace("60 0 0 0.1", "");
+    assert!(matches!(
+        TabulatedBrdf::parse(&missing_row),
+        Err(BrdfError::Grid(_))
+    ));
+    assert!(matches!(
+        TabulatedBrdf::parse("0 0 0\n"),
+        Err(BrdfError::Parse { line: 1, .. })
+    ));
+}
+
+#[test]
+#[should_panic(expected = "BRDF")]
+fn brdf_emissivity_comes_from_the_table() {
+    let brdf = tabulate(&[0.], 30., |_, _, _| 0.2 / PI);
+    Surface::new_disk(Vec3::ZERO, 1., Vec3::Z).set_side_optics(
+        Side::Front,
+        0.5,
+        Material::Brdf(Arc::new(brdf)),
+    );
+}
+
+#[test]
+fn brdf_surfaces_have_no_band_emissivities() {
+    let brdf = tabulate(&[0.], 30., |_, _, _| 0.2 / PI);
+    let surface = Surface::new_disk(Vec3::ZERO, 1., Vec3::Z).set_brdf(brdf);
+    assert!(matches!(
+        surface.set_band_emissivity(1, 0.5),
+        Err(BandError::Brdf { band: 1 })
+    ));
+}
+
+#[test]
+#[should_panic(expected = "BRDF surfaces cannot have band emissivities")]
+fn band_emissivities_rule_out_brdfs() {
+    let brdf = tabulate(&[0.], 30., |_, _, _| 0.2 / PI);
+    Surface::new_disk(Vec3::ZERO, 1., Vec3::Z)
+        .set_band_emissivity(0, 0.5)
+        .unwrap()
+        .set_brdf(brdf);
+}
+
+#[test]
+fn tabulated_exchange_is_reciprocal() {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(14));
+
+    // Reflectance falls from 0.6 at normal incidence to 0.2 at grazing
+    let fading = tabulate(&[0., 90.], 10., |theta_i, _, _| {
+        (0.6 - 0.4 * theta_i / (PI / 2.)) / PI
+    });
+    let glossy = tabulate(&[0., 45., 90.], 5., |theta_i, theta_r, phi| {
+        let lobe = (-(theta_r - theta_i).powi(2) / 0.05 - phi * phi / 0.2).exp();
+        0.5 * lobe / (1. + theta_i)
+    });
+
+    scene
+        .add_surface(Surface::new_rectangle(Vec3::ZERO, 1., 1., Vec3::Z, Vec3::X).set_brdf(fading));
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::Z, 2., 2., Vec3::NEG_Z, Vec3::X).set_brdf(glossy),
+    );
+
+    // Each surface emits in proportion to its hemispherical emissivity and area
+    let matrix = scene.view_factor_matrix(1_000_000);
+    let exchange = |i: usize, j: usize| {
+        let surface = &scene.surfaces()[i];
+        surface.front.emissivity * surface.area() * matrix[i][j].mean
+    };
+    assert!((exchange(0, 1) / exchange(1, 0) - 1.).abs() < 0.02);
+}
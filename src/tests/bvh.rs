@@ -0,0 +1,134 @@
+#![cfg(test)]
+
+use crate::{
+    primitives::{Cone, Cube, Cylinder},
+    surfaces::Surface,
+    Point, Ray, Scene,
+};
+
+use glam::Vec3;
+use rand::{thread_rng, Rng};
+
+fn random_vec3(rng: &mut impl Rng, scale: f32) -> Vec3 {
+    Vec3::new(
+        rng.gen_range(-scale..scale),
+        rng.gen_range(-scale..scale),
+        rng.gen_range(-scale..scale),
+    )
+}
+
+// Scene with every kind of collider, including many overlapping triangles
+fn cluttered_scene() -> Scene {
+    let mut rng = thread_rng();
+    let mut scene = Scene::default();
+
+    for _ in 0..500 {
+        let a = random_vec3(&mut rng, 5.);
+        scene.add_surface(Surface::new_triangle(
+            a,
+            a + random_vec3(&mut rng, 0.5),
+            a + random_vec3(&mut rng, 0.5),
+        ));
+    }
+
+    for _ in 0..20 {
+        scene.add_surface(Surface::new_sphere(random_vec3(&mut rng, 5.), 0.3));
+        scene.add_surface(Surface::new_disk(
+            random_vec3(&mut rng, 5.),
+            0.5,
+            random_vec3(&mut rng, 1.).normalize(),
+        ));
+        scene.add_primitive(Cube {
+            translation: random_vec3(&mut rng, 5.),
+            axis_y: Vec3::Y,
+            axis_x: Vec3::X,
+            scale: Vec3::new(0.5, 0.3, 0.2),
+        });
+        scene.add_primitive(Cylinder {
+            translation: random_vec3(&mut rng, 5.),
+            axis: random_vec3(&mut rng, 1.),
+            height: 1.,
+            radius: 0.2,
+        });
+        scene.add_primitive(Cone {
+            translation: random_vec3(&mut rng, 5.),
+            axis: random_vec3(&mut rng, 1.),
+            height: 1.,
+            radius: 0.4,
+        });
+    }
+
+    scene
+}
+
+#[test]
+fn bvh_matches_brute_force() {
+    let mut rng = thread_rng();
+    let mut scene = cluttered_scene();
+
+    for round in 0..2 {
+        for _ in 0..20_000 {
+            let ray = Ray {
+                origin: Point(random_vec3(&mut rng, 7.)),
+                direction: random_vec3(&mut rng, 1.),
+            };
+
+            let hit = scene.cast_ray(&ray);
+            let expected = scene.cast_ray_brute_force(&ray);
+
+            match (hit, expected) {
+                (None, None) => {}
+                (Some(hit), Some(expected)) => {
+                    assert_eq!(hit.surface_id, expected.surface_id);
+                    assert_eq!(hit.hit.t, expected.hit.t);
+                }
+                (hit, expected) => panic!("BVH found {hit:?} but brute force found {expected:?}"),
+            }
+        }
+
+        // Adding surfaces after casting must rebuild the BVH
+        if round == 0 {
+            scene.add_surface(Surface::new_sphere(20. * Vec3::X, 1.));
+            assert_eq!(
+                scene
+                    .cast_ray(&Ray {
+                        origin: Point(30. * Vec3::X),
+                        direction: Vec3::NEG_X,
+                    })
+                    .map(|hit| hit.surface_id),
+                Some(scene.surfaces().len() - 1),
+            );
+        }
+    }
+}
+
+#[test]
+fn surfaces_edited_in_place() {
+    let mut scene = Scene::default();
+    scene.add_surface(Surface::new_sphere(Vec3::ZERO, 1.));
+    let moved = scene.add_surface(Surface::new_sphere(20. * Vec3::X, 1.));
+
+    let ray = Ray {
+        origin: Point(30. * Vec3::X),
+        direction: Vec3::NEG_X,
+    };
+    let hit_surface = |scene: &Scene| scene.cast_ray(&ray).map(|hit| hit.surface_id);
+    assert_eq!(hit_surface(&scene), Some(1));
+
+    // Replacing a surface after rays have been cast is seen by the next cast
+    *scene.surface_mut(moved) = Surface::new_sphere(-20. * Vec3::X, 1.);
+    assert_eq!(hit_surface(&scene), Some(0));
+}
+
+#[test]
+fn surface_bounds_contain_samples() {
+    let scene = cluttered_scene();
+
+    for surface in scene.surfaces() {
+        let bounds = surface.bounds().padded();
+        for _ in 0..100 {
+            let position = surface.sample(&mut thread_rng()).position.0;
+            assert!(position.cmpge(bounds.min).all() && position.cmple(bounds.max).all());
+        }
+    }
+}
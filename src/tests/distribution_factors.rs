@@ -1,4 +1,7 @@
-use crate::{surfaces::Surface, Scene};
+use crate::{
+    surfaces::{Material, Side, Surface},
+    Scene, SurfaceId,
+};
 
 use glam::Vec3;
 
@@ -20,7 +23,7 @@ pub fn infinite_plates(e1: f32, e2: f32) -> (f32, f32) {
         Surface::new_rectangle(Vec3::Z, length, length, Vec3::NEG_Z, Vec3::NEG_X).to_gray_body(e2),
     );
 
-    let v = scene.view_factors_for_surface(0, 10_000)[1];
+    let v = scene.view_factors_for_surface(SurfaceId(0), 10_000)[1].mean;
 
     let v_an = e2 / (e1 + e2 - e1 * e2);
 
@@ -56,13 +59,100 @@ pub fn cube_enclosure(e: f32) -> Vec<f32> {
     scene.add_surface(Surface::new_rectangle(-k * 0.5, 1., 1., k, i).to_gray_body(e));
 
     //scene.debug_rays_from_surface(0, 10000);
-    let v = scene.view_factors_for_surface(2, 10_000);
+    let v = scene.view_factors_for_surface(SurfaceId(2), 10_000).means();
 
     dbg!(v)
 }
 
+/// Fraction of the emission from a black disk of unit radius that comes back to it after a single
+/// reflection from a non-absorbing floor a unit distance below it
+pub fn disk_over_reflector(material: Material) -> f32 {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(9));
+
+    scene.add_surface(Surface::new_disk(Vec3::Z, 1., Vec3::NEG_Z));
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::ZERO, 1000., 1000., Vec3::Z, Vec3::X).set_side_optics(
+            Side::Front,
+            0.,
+            material,
+        ),
+    );
+
+    scene.view_factors_for_surface(SurfaceId(0), 500_000)[0].mean
+}
+
+/// Black unit disks two units apart on either side of a large pane that absorbs 10% and transmits
+/// 60%, returning the view factors from the lower disk to itself, the pane, and the upper disk
+pub fn disks_through_pane() -> Vec<f32> {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(10));
+
+    scene.add_surface(Surface::new_disk(Vec3::ZERO, 1., Vec3::Z));
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::Z, 1000., 1000., Vec3::NEG_Z, Vec3::X)
+            .to_gray_body(0.1)
+            .set_transmissive(0.6, None),
+    );
+    scene.add_surface(Surface::new_disk(2. * Vec3::Z, 1., Vec3::NEG_Z));
+
+    scene
+        .view_factors_for_surface(SurfaceId(0), 500_000)
+        .means()
+}
+
+/// Black unit disk under a clear pane one unit up and a large mirror two units up, returning the
+/// view factor from the disk to itself after its light comes back through the pane from behind
+pub fn disk_through_pane_and_back() -> f32 {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(18));
+
+    scene.add_surface(Surface::new_disk(Vec3::ZERO, 1., Vec3::Z));
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::Z, 1000., 1000., Vec3::NEG_Z, Vec3::X)
+            .to_gray_body(0.)
+            .set_transmissive(1., None),
+    );
+    scene.add_surface(
+        Surface::new_rectangle(2. * Vec3::Z, 1000., 1000., Vec3::NEG_Z, Vec3::X)
+            .to_gray_body(0.)
+            .set_specular(),
+    );
+
+    scene.view_factors_for_surface(SurfaceId(0), 500_000)[0].mean
+}
+
+/// Fraction of the emission from a black disk inside glass that escapes through a flat interface,
+/// to a large black plate above it
+pub fn escape_from_glass(refractive_index: f32) -> f32 {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(11));
+
+    scene.add_surface(Surface::new_disk(Vec3::ZERO, 1., Vec3::Z));
+
+    // Glass lies behind the interface, which faces out towards the plate
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::Z, 1000., 1000., Vec3::Z, Vec3::X)
+            .to_gray_body(0.)
+            .set_transmissive(1., Some(refractive_index)),
+    );
+    scene.add_surface(Surface::new_rectangle(
+        2. * Vec3::Z,
+        1000.,
+        1000.,
+        Vec3::NEG_Z,
+        Vec3::X,
+    ));
+
+    scene.view_factors_for_surface(SurfaceId(0), 500_000)[2].mean
+}
+
 #[cfg(test)]
 mod test {
+    use crate::surfaces::{ActiveSides, Material, Surface};
+
+    use glam::Vec3;
+
     // Helper function for computing percent error
     fn percent_error((expected, measured): (f32, f32)) -> f32 {
         100. * (measured - expected) / expected
@@ -72,4 +162,86 @@ mod test {
     fn infinite_plates() {
         assert!(percent_error(super::infinite_plates(0.2, 0.7)) < 1.)
     }
+
+    #[test]
+    fn mixed_reflections() {
+        // A mirror shows the disk its own image, a coaxial disk twice as far away
+        let specular = super::disk_over_reflector(Material::Specular);
+        let x: f32 = 1. + (1. + 0.25) / 0.25;
+        let image = 0.5 * (x - (x * x - 4.).sqrt());
+        assert!(percent_error((image, specular)).abs() < 1.);
+
+        // Each reflection picks one of the two behaviors, so the result is their weighted average
+        let diffuse = super::disk_over_reflector(Material::Diffuse);
+        let mixed = super::disk_over_reflector(Material::Mixed { specularity: 0.3 });
+        assert!(percent_error((0.3 * specular + 0.7 * diffuse, mixed)).abs() < 1.);
+    }
+
+    #[test]
+    #[should_panic(expected = "Specularity")]
+    fn specularity_is_a_fraction() {
+        Surface::new_disk(Vec3::ZERO, 1., Vec3::Z).set_mixed(1.5);
+    }
+
+    #[test]
+    fn transmission_through_pane() {
+        let x: f32 = 1. + (1. + 0.25) / 0.25;
+        let direct = 0.5 * (x - (x * x - 4.).sqrt());
+
+        // The pane passes its share of the direct exchange, and mirrors the lower disk onto itself
+        let v = super::disks_through_pane();
+        assert!(percent_error((0.3 * direct, v[0])).abs() < 1.);
+        assert!(percent_error((0.1, v[1])).abs() < 1.);
+        assert!(percent_error((0.6 * direct, v[2])).abs() < 1.);
+    }
+
+    #[test]
+    fn transmission_from_behind() {
+        // The mirror shows the disk its own image four units away, seen through both sides of the
+        // pane
+        let x: f32 = 1. + (1. + 0.0625) / 0.0625;
+        let image = 0.5 * (x - (x * x - 4.).sqrt());
+        assert!(percent_error((image, super::disk_through_pane_and_back())).abs() < 1.);
+    }
+
+    #[test]
+    #[should_panic(expected = "both sides active")]
+    fn transmissive_surfaces_stay_two_sided() {
+        Surface::new_disk(Vec3::ZERO, 1., Vec3::Z)
+            .to_gray_body(0.1)
+            .set_transmissive(0.6, None)
+            .set_active_sides(ActiveSides::Front);
+    }
+
+    #[test]
+    fn total_internal_reflection() {
+        // Only directions inside the critical angle escape, which is 1 / n^2 of diffuse emission
+        let n = 1.5;
+        assert!(percent_error((1. / (n * n), super::escape_from_glass(n))).abs() < 1.);
+    }
+
+    #[test]
+    fn snells_law() {
+        let normal = Surface::new_disk(Vec3::ZERO, 1., Vec3::Z)
+            .sample(&mut rand::thread_rng())
+            .normal;
+        let incident = Vec3::new(0.5, 0., -(0.75f32).sqrt());
+
+        let refracted = normal.to_refracted_ray(incident, 1. / 1.5).unwrap();
+        assert!((refracted.length() - 1.).abs() < 1e-5);
+        assert!((refracted.x - 0.5 / 1.5).abs() < 1e-5);
+        assert!(refracted.z < 0.);
+
+        // Past the critical angle going from glass to air
+        let grazing = Vec3::new((0.75f32).sqrt(), 0., -0.5);
+        assert!(normal.to_refracted_ray(grazing, 1.5).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "must not add up to more than 1")]
+    fn transmissivity_leaves_room_for_absorption() {
+        Surface::new_disk(Vec3::ZERO, 1., Vec3::Z)
+            .to_gray_body(0.5)
+            .set_transmissive(0.6, None);
+    }
 }
@@ -0,0 +1,121 @@
+#![cfg(test)]
+
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+use crate::{
+    surfaces::{DirectionalEmissivity, EmissivityModel, Surface},
+    Scene, SurfaceId,
+};
+
+use glam::Vec3;
+
+#[test]
+fn emissivity_models() {
+    // Normal incidence matches the simple Fresnel reflectance
+    let n = 1.5;
+    let glass = DirectionalEmissivity::new(EmissivityModel::Dielectric {
+        refractive_index: n,
+    });
+    let normal = 1. - ((n - 1.) / (n + 1.)).powi(2);
+    assert!((glass.at(1.) - normal).abs() < 1e-5);
+    assert!(glass.at(0.) < 1e-3);
+    assert!(glass.hemispherical() < normal && glass.hemispherical() > 0.85);
+
+    let (n, k) = (1.5, 7.6);
+    let aluminum = DirectionalEmissivity::new(EmissivityModel::Metal {
+        refractive_index: n,
+        extinction_coefficient: k,
+    });
+    let normal = 1. - ((n - 1.).powi(2) + k * k) / ((n + 1.).powi(2) + k * k);
+    assert!((aluminum.at(1.) - normal).abs() < 1e-5);
+
+    // Metals emit most strongly near grazing angles
+    assert!(aluminum.at(0.1) > 1.5 * aluminum.at(1.));
+
+    let table =
+        DirectionalEmissivity::new(EmissivityModel::Table(vec![(0., 0.8), (FRAC_PI_2, 0.4)]));
+    assert!((table.at(FRAC_PI_4.cos()) - 0.6).abs() < 1e-3);
+
+    let gray = DirectionalEmissivity::new(EmissivityModel::Table(vec![(0., 0.3)]));
+    assert!((gray.hemispherical() - 0.3).abs() < 1e-6);
+}
+
+#[test]
+fn emission_follows_emissivity() {
+    // Only directions within 45 degrees of the normal emit
+    let cone = DirectionalEmissivity::new(EmissivityModel::Table(vec![
+        (0., 1.),
+        (FRAC_PI_4, 1.),
+        (FRAC_PI_4 + 1e-4, 0.),
+    ]));
+    let normal = Surface::new_disk(Vec3::ZERO, 1., Vec3::Z)
+        .sample(&mut rand::thread_rng())
+        .normal;
+
+    let mut rng = rand::thread_rng();
+    let num_samples = 100_000;
+    let mut within_30_degrees = 0;
+    for _ in 0..num_samples {
+        let direction = cone.sample_direction(&normal, &mut rng);
+        assert!(direction.z > (FRAC_PI_4 + 1e-3).cos());
+        if direction.z > 0.75f32.sqrt() {
+            within_30_degrees += 1;
+        }
+    }
+
+    // Diffuse emission is uniform in sin^2, so half of it lies within 30 degrees
+    let fraction = within_30_degrees as f32 / num_samples as f32;
+    assert!((fraction - 0.5).abs() < 0.01);
+}
+
+#[test]
+fn absorption_depends_on_incidence() {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(12));
+
+    // Tiny diffuse source over a smooth glass floor, which reflects what it does not absorb away
+    scene.add_surface(Surface::new_disk(Vec3::Z, 0.01, Vec3::NEG_Z));
+    let model = EmissivityModel::Dielectric {
+        refractive_index: 1.5,
+    };
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::ZERO, 10_000., 10_000., Vec3::Z, Vec3::X)
+            .set_specular()
+            .set_directional_emissivity(model.clone()),
+    );
+
+    // Diffuse rays arrive with the same weighting that defines the hemispherical emissivity
+    let expected = DirectionalEmissivity::new(model).hemispherical();
+    let absorbed = scene.view_factors_for_surface(SurfaceId(0), 200_000)[1].mean;
+    assert!((absorbed / expected - 1.).abs() < 0.01);
+}
+
+#[test]
+fn directional_exchange_is_reciprocal() {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(13));
+
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::ZERO, 1., 1., Vec3::Z, Vec3::X)
+            .set_specular()
+            .set_directional_emissivity(EmissivityModel::Dielectric {
+                refractive_index: 1.5,
+            }),
+    );
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::Z, 2., 2., Vec3::NEG_Z, Vec3::X)
+            .set_specular()
+            .set_directional_emissivity(EmissivityModel::Metal {
+                refractive_index: 1.5,
+                extinction_coefficient: 7.6,
+            }),
+    );
+
+    // Each surface emits in proportion to its hemispherical emissivity and area
+    let matrix = scene.view_factor_matrix(1_000_000);
+    let exchange = |i: usize, j: usize| {
+        let surface = &scene.surfaces()[i];
+        surface.front.emissivity * surface.area() * matrix[i][j].mean
+    };
+    assert!((exchange(0, 1) / exchange(1, 0) - 1.).abs() < 0.02);
+}
@@ -0,0 +1,259 @@
+#![cfg(test)]
+
+use crate::{
+    primitives::{Cube, Mesh, MeshNodes},
+    surfaces::Surface,
+    tests::equal_rectangular_plates,
+    Scene,
+};
+
+use glam::{Mat4, Vec3};
+
+#[test]
+fn primitives_return_their_surfaces() {
+    let mut scene = Scene::default();
+    scene.add_surface(Surface::new_sphere(Vec3::ZERO, 0.1));
+
+    let faces = scene.add_primitive(Cube {
+        translation: 5. * Vec3::X,
+        axis_y: Vec3::Y,
+        axis_x: Vec3::X,
+        scale: Vec3::ONE,
+    });
+
+    let indices: Vec<usize> = faces.iter().map(|face| face.index()).collect();
+    assert_eq!(indices, vec![1, 2, 3, 4, 5, 6]);
+
+    // The +x face comes first
+    let sample = scene.surface(faces[0]).sample(&mut rand::thread_rng());
+    assert!((sample.position.0.x - 5.5).abs() < 1e-5);
+}
+
+#[test]
+fn surface_names() {
+    let mut scene = Scene::default();
+    let disk = scene.add_named_surface("disk", Surface::new_disk(Vec3::ZERO, 1., Vec3::Z));
+    let sphere = scene.add_surface(Surface::new_sphere(Vec3::Z, 0.5));
+
+    assert_eq!(scene.surface_name(disk), Some("disk"));
+    assert_eq!(scene.surface_name(sphere), None);
+    assert_eq!(scene.find_surface("disk"), Some(disk));
+
+    scene.set_surface_name(sphere, "sphere");
+    assert_eq!(scene.find_surface("sphere"), Some(sphere));
+    assert_eq!(scene.find_surface("cone"), None);
+}
+
+#[test]
+#[should_panic(expected = "already exists")]
+fn surface_names_are_unique() {
+    let mut scene = Scene::default();
+    scene.add_named_surface("disk", Surface::new_disk(Vec3::ZERO, 1., Vec3::Z));
+    scene.add_named_surface("disk", Surface::new_disk(Vec3::Z, 1., Vec3::Z));
+}
+
+#[test]
+#[should_panic(expected = "already in a group")]
+fn surfaces_belong_to_one_group() {
+    let mut scene = Scene::default();
+    let disk = scene.add_surface(Surface::new_disk(Vec3::ZERO, 1., Vec3::Z));
+    scene.add_group("a", &[disk]);
+    scene.add_group("b", &[disk]);
+}
+
+#[test]
+#[should_panic(expected = "has no area")]
+fn groups_need_area() {
+    // Constructors reject empty surfaces, but a transform can still be collapsed afterwards
+    let mut disk = Surface::new_disk(Vec3::ZERO, 1., Vec3::Z);
+    disk.transform.m = Mat4::ZERO;
+
+    let mut scene = Scene::default();
+    let point = scene.add_surface(disk);
+    scene.add_group("point", &[point]);
+}
+
+// Two opposed unit squares a unit apart, each made of two strips, plus an ungrouped sphere
+fn split_parallel_squares() -> Scene {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(4));
+
+    let lower = [
+        scene.add_surface(Surface::new_rectangle(
+            -0.25 * Vec3::X,
+            0.5,
+            1.,
+            Vec3::Z,
+            Vec3::X,
+        )),
+        scene.add_surface(Surface::new_rectangle(
+            0.25 * Vec3::X,
+            0.5,
+            1.,
+            Vec3::Z,
+            Vec3::X,
+        )),
+    ];
+    let upper = [
+        scene.add_surface(Surface::new_rectangle(
+            Vec3::new(-0.25, 0., 1.),
+            0.5,
+            1.,
+            Vec3::NEG_Z,
+            Vec3::X,
+        )),
+        scene.add_surface(Surface::new_rectangle(
+            Vec3::new(0.25, 0., 1.),
+            0.5,
+            1.,
+            Vec3::NEG_Z,
+            Vec3::X,
+        )),
+    ];
+    scene.add_surface(Surface::new_sphere(10. * Vec3::X, 0.1));
+
+    scene.add_group("lower", &lower);
+    scene.add_group("upper", &upper);
+
+    scene
+}
+
+#[test]
+fn group_view_factors() {
+    let scene = split_parallel_squares();
+    let (analytic, _) = equal_rectangular_plates(1., 1.);
+
+    let upper = scene.find_group("upper").unwrap();
+    assert_eq!(scene.group(upper).surfaces.len(), 2);
+    assert_eq!(scene.group_of(scene.group(upper).surfaces[1]), Some(upper));
+
+    // Each strip sees the whole of the opposite square
+    let matrix = scene.view_factor_matrix(400_000);
+    let strip = scene.view_factors_to_groups(&matrix[0]);
+    assert!((strip[1] / analytic - 1.).abs() < 0.01);
+    assert_eq!(strip[0], 0.);
+
+    let groups = scene.group_view_factor_matrix(&matrix);
+    assert_eq!(groups.len(), 2);
+    assert!((groups[0][1] / analytic - 1.).abs() < 0.01);
+    assert!((groups[1][0] / analytic - 1.).abs() < 0.01);
+}
+
+#[test]
+fn mesh_nodes_become_groups() {
+    let obj = "
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 0 0 1
+        v 1 0 1
+        v 1 1 1
+        v 0 1 1
+
+        g lower
+        f 1 2 3
+        f 1 3 4
+        g upper
+        f 5 8 7 6
+    ";
+    let mesh = Mesh::parse_obj(obj).unwrap().with_nodes(MeshNodes::Groups);
+
+    let mut scene = Scene::default();
+    let surfaces = scene.add_primitive(mesh.clone());
+    for node in mesh.nodes() {
+        node.add_group(&mut scene, &surfaces);
+    }
+
+    let lower = scene.find_group("lower").unwrap();
+    assert_eq!(scene.group(lower).surfaces, surfaces[..2]);
+    assert_eq!(scene.group_of(surfaces[2]), scene.find_group("upper"));
+}
+
+#[test]
+fn named_meshes_group_side_by_side() {
+    let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3";
+    let mut scene = Scene::default();
+
+    // Every face of both meshes becomes a node, which would share names without the mesh names
+    for name in ["left", "right"] {
+        let mesh = Mesh::parse_obj(obj).unwrap().with_name(name);
+        let surfaces = scene.add_primitive(mesh.clone());
+        for node in mesh.nodes() {
+            node.add_group(&mut scene, &surfaces);
+        }
+    }
+
+    let left = scene.find_group("left/face_0").unwrap();
+    let right = scene.find_group("right/face_0").unwrap();
+    assert_ne!(scene.group(left).surfaces, scene.group(right).surfaces);
+}
+
+#[test]
+fn traced_group_view_factors_weight_members_by_area() {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(5));
+
+    // Lower square made of unequal strips, so each strip sees a different share of the upper one
+    let lower = [
+        scene.add_surface(Surface::new_rectangle(
+            Vec3::new(-0.375, 0., 0.),
+            0.25,
+            1.,
+            Vec3::Z,
+            Vec3::X,
+        )),
+        scene.add_surface(Surface::new_rectangle(
+            Vec3::new(0.125, 0., 0.),
+            0.75,
+            1.,
+            Vec3::Z,
+            Vec3::X,
+        )),
+    ];
+    let upper = scene.add_surface(Surface::new_rectangle(
+        Vec3::Z,
+        1.,
+        1.,
+        Vec3::NEG_Z,
+        Vec3::X,
+    ));
+    scene.add_surface(Surface::new_sphere(Vec3::new(0., 0., 0.5), 0.01));
+
+    let lower = scene.add_group("lower", &lower);
+    scene.add_group("upper", &[upper]);
+
+    let (analytic, _) = equal_rectangular_plates(1., 1.);
+    let view_factors = scene.view_factors_for_group(lower, 1_000_000);
+
+    // Only groups are reported, so the ungrouped sphere is left out
+    assert_eq!(view_factors.estimates.len(), 2);
+    assert_eq!(view_factors[0].mean, 0.);
+    assert!((view_factors[1].mean / analytic - 1.).abs() < 0.01);
+}
+
+#[test]
+fn cube_as_a_single_node() {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(6));
+
+    let faces = scene.add_primitive(Cube {
+        translation: Vec3::ZERO,
+        axis_y: Vec3::Y,
+        axis_x: Vec3::X,
+        scale: Vec3::new(1., 0.5, 2.),
+    });
+    let cube = scene.add_group("cube", &faces);
+
+    let shell = scene.add_surface(Surface::new_sphere(Vec3::ZERO, 3.).flip_normals());
+    let shell = scene.add_group("shell", &[shell]);
+
+    // Everything the cube emits reaches the shell, so reciprocity fixes the other direction
+    let matrix = scene.view_factor_matrix_for_groups(200_000);
+    let cube_area = 2. * (0.5 + 2. + 1.);
+    let shell_area = 4. * std::f32::consts::PI * 9.;
+
+    assert!((matrix[cube.index()][shell.index()].mean - 1.).abs() < 1e-6);
+    let expected = cube_area / shell_area;
+    assert!((matrix[shell.index()][cube.index()].mean / expected - 1.).abs() < 0.02);
+}
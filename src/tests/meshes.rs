@@ -0,0 +1,302 @@
+use crate::{
+    primitives::{Mesh, StlOrientation},
+    Scene, SurfaceId,
+};
+
+/// Analytical Source: http://webserver.dmt.upm.es/~isidoro/tc3/Radiation%20View%20factors.pdf
+/// Two directly opposed unit squares with a unit gap, imported from an OBJ file
+pub fn obj_parallel_squares() -> (f32, f32) {
+    let obj = "
+        # Lower square faces up, upper square faces down
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 0 0 1
+        v 1 0 1
+        v 1 1 1
+        v 0 1 1
+
+        g lower
+        f 1 2 3 4
+        g upper
+        f 5/1/1 8/1/1 7/1/1 6/1/1
+    ";
+
+    let mut scene = Scene::default();
+    scene.add_primitive(Mesh::parse_obj(obj).unwrap());
+
+    let v = scene.view_factors_for_surface(SurfaceId(0), 1_000_000)[1].mean;
+
+    let (v_an, _) = super::equal_rectangular_plates(1., 1.);
+
+    (v_an, v)
+}
+
+/// Analytical Source: http://webserver.dmt.upm.es/~isidoro/tc3/Radiation%20View%20factors.pdf
+/// Two directly opposed unit squares with a unit gap, imported from an ASCII STL file
+/// The upper square's facets are wound the wrong way, so their stored normals must be trusted
+pub fn stl_parallel_squares() -> (f32, f32) {
+    let stl = "
+        solid lower
+          facet normal 0 0 1
+            outer loop
+              vertex 0 0 0
+              vertex 1 0 0
+              vertex 1 1 0
+            endloop
+          endfacet
+          facet normal 0 0 1
+            outer loop
+              vertex 0 0 0
+              vertex 1 1 0
+              vertex 0 1 0
+            endloop
+          endfacet
+        endsolid lower
+        solid upper
+          facet normal 0 0 -1
+            outer loop
+              vertex 0 0 1
+              vertex 1 0 1
+              vertex 1 1 1
+            endloop
+          endfacet
+          facet normal 0 0 -1
+            outer loop
+              vertex 0 0 1
+              vertex 1 1 1
+              vertex 0 1 1
+            endloop
+          endfacet
+        endsolid upper
+    ";
+
+    let mut scene = Scene::default();
+    scene.add_primitive(Mesh::parse_stl(stl.as_bytes(), StlOrientation::FromNormals).unwrap());
+
+    // Emit from both lower triangles, which have equal areas
+    let lower_0 = scene.view_factors_for_surface(SurfaceId(0), 500_000);
+    let lower_1 = scene.view_factors_for_surface(SurfaceId(1), 500_000);
+    let v = (lower_0[2].mean + lower_0[3].mean + lower_1[2].mean + lower_1[3].mean) / 2.;
+
+    let (v_an, _) = super::equal_rectangular_plates(1., 1.);
+
+    (v_an, v)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        primitives::{Mesh, MeshError, MeshNodes, StlOrientation},
+        Scene,
+    };
+
+    // Helper function for computing percent error
+    fn percent_error((expected, measured): (f32, f32)) -> f32 {
+        100. * (measured - expected) / expected
+    }
+
+    // Inward facing unit cube split into two groups, with one face given as two triangles
+    const CUBE: &str = "
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 0 0 1
+        v 1 0 1
+        v 1 1 1
+        v 0 1 1
+
+        o sides
+        usemtl paint
+        f 5 6 2 1
+        f 6 7 3 2
+        f 7 8 4 3
+        f -5 -1 -4 -8
+        o caps
+        usemtl foil
+        f 3 4 1
+        f 2 3 1
+        usemtl paint
+        f 8 7 6 5
+    ";
+
+    #[test]
+    fn obj_parallel_squares() {
+        assert!(percent_error(super::obj_parallel_squares()).abs() < 1.)
+    }
+
+    #[test]
+    fn obj_faces_to_surfaces() {
+        let mesh = Mesh::parse_obj(CUBE).unwrap();
+        assert_eq!(mesh.num_faces(), 7);
+
+        let mut scene = Scene::default();
+        let faces = scene.add_primitive(mesh.clone());
+        assert_eq!(scene.surfaces().len(), 7);
+
+        // Closed black cube, so every ray must be absorbed by one of its faces
+        let v: f32 = scene
+            .view_factors_for_surface(faces[0], 10_000)
+            .means()
+            .iter()
+            .sum();
+        assert!((v - 1.0).abs() < 1e-3);
+
+        let groups = mesh.clone().with_nodes(MeshNodes::Groups).nodes();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].name, "sides");
+        assert_eq!(groups[0].surfaces, vec![0, 1, 2, 3]);
+        assert_eq!(groups[1].surfaces, vec![4, 5, 6]);
+
+        let materials = mesh.with_nodes(MeshNodes::Materials).nodes();
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].surfaces, vec![0, 1, 2, 3, 6]);
+        assert_eq!(materials[1].name, "foil");
+    }
+
+    #[test]
+    fn obj_errors() {
+        assert!(Mesh::parse_obj("v 0 0 0\nv 1 0 0\nf 1 2").is_err());
+        assert!(Mesh::parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4").is_err());
+        assert!(Mesh::parse_obj("v 0 0\n").is_err());
+
+        // Triangles too thin for a surface are rejected like degenerate STL facets
+        assert!(matches!(
+            Mesh::parse_obj("v 0 0 0\nv 1 0 0\nv 2 1e-9 0\nf 1 2 3"),
+            Err(MeshError::DegenerateFacet { facet: 0 })
+        ));
+    }
+
+    #[test]
+    fn mesh_files_name_their_nodes() {
+        let path = std::env::temp_dir().join("mesh_files_name_their_nodes.obj");
+        std::fs::write(&path, "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3").unwrap();
+        let mesh = Mesh::from_obj(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(mesh.nodes()[0].name, "mesh_files_name_their_nodes/face_0");
+    }
+
+    #[test]
+    fn obj_non_convex_face() {
+        // L shaped face whose fan from the first corner would cover the missing quadrant
+        let obj = "
+            v 0 0 0
+            v 2 0 0
+            v 2 1 0
+            v 1 1 0
+            v 1 2 0
+            v 0 2 0
+            f 4 5 6 1 2 3
+        ";
+
+        let mut scene = Scene::default();
+        scene.add_primitive(Mesh::parse_obj(obj).unwrap());
+        assert_eq!(scene.surfaces().len(), 4);
+
+        let area: f32 = scene.surfaces().iter().map(|surface| surface.area()).sum();
+        assert!((area - 3.).abs() < 1e-5);
+
+        // No triangle reaches into the missing quadrant
+        let mut rng = rand::thread_rng();
+        for surface in scene.surfaces() {
+            for _ in 0..100 {
+                let position = surface.sample(&mut rng).position.0;
+                assert!(position.x <= 1. + 1e-5 || position.y <= 1. + 1e-5);
+            }
+        }
+
+        // Faces that cross themselves cannot be triangulated
+        let bowtie = "v 0 0 0\nv 1 1 0\nv 1 0 0\nv 0 1 0\nf 1 2 3 4";
+        assert!(Mesh::parse_obj(bowtie).is_err());
+    }
+
+    #[test]
+    fn stl_parallel_squares() {
+        assert!(percent_error(super::stl_parallel_squares()).abs() < 1.)
+    }
+
+    #[test]
+    fn ascii_stl_solids() {
+        let solid = "
+            solid plate
+              facet normal 0 0 1
+                outer loop
+                  vertex 0 0 0
+                  vertex 1 0 0
+                  vertex 0 1 0
+                endloop
+              endfacet
+            endsolid plate
+        ";
+
+        // Solids that share a name are merged into one group
+        let mesh = Mesh::parse_stl(solid.repeat(2).as_bytes(), StlOrientation::FromNormals)
+            .unwrap()
+            .with_nodes(MeshNodes::Groups);
+        let nodes = mesh.nodes();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(
+            (nodes[0].name.as_str(), nodes[0].surfaces.len()),
+            ("plate", 2)
+        );
+
+        // Text that is not UTF-8 is a parse error on the line it appears, here the one after the
+        // solid's ten lines
+        let mut bytes = solid.as_bytes().to_vec();
+        bytes.extend(b"\xff\n");
+        assert!(matches!(
+            Mesh::parse_stl(&bytes, StlOrientation::FromNormals),
+            Err(MeshError::Parse { line: 11, .. })
+        ));
+    }
+
+    // Binary STL with a single facet whose stored normal disagrees with its winding
+    fn flipped_binary_facet() -> Vec<u8> {
+        let mut bytes = vec![0u8; 80];
+        bytes.extend(1u32.to_le_bytes());
+        for value in [0., 0., -1., 0., 0., 0., 1., 0., 0., 0., 1., 0.] {
+            bytes.extend((value as f32).to_le_bytes());
+        }
+        bytes.extend(0u16.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn stl_orientation() {
+        let bytes = flipped_binary_facet();
+
+        // Counter-clockwise winding faces +z, so only the stored normal can point the ray down
+        let facing = |orientation| {
+            let mut scene = Scene::default();
+            scene.add_primitive(Mesh::parse_stl(&bytes, orientation).unwrap());
+            let sample = scene.surfaces()[0].sample(&mut rand::thread_rng());
+            sample.normal.vec().z
+        };
+
+        assert!(facing(StlOrientation::FromNormals) < -0.99);
+        assert!(facing(StlOrientation::FromWinding) > 0.99);
+        assert!(matches!(
+            Mesh::parse_stl(&bytes, StlOrientation::Strict),
+            Err(MeshError::Orientation { facet: 0 })
+        ));
+
+        // Truncated binary data is rejected
+        assert!(Mesh::parse_stl(&bytes[..bytes.len() - 1], StlOrientation::FromNormals).is_err());
+
+        // So are facet counts whose size overflows, and facets with no area
+        let mut overflowing = bytes.clone();
+        overflowing[80..84].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(Mesh::parse_stl(&overflowing, StlOrientation::FromNormals).is_err());
+
+        // Squash the third vertex onto the x axis
+        let mut sliver = bytes;
+        sliver[124..128].copy_from_slice(&1e-9f32.to_le_bytes());
+        assert!(matches!(
+            Mesh::parse_stl(&sliver, StlOrientation::FromNormals),
+            Err(MeshError::DegenerateFacet { facet: 0 })
+        ));
+    }
+}
@@ -1,5 +1,26 @@
+mod areas;
+mod bands;
+mod bins;
+mod brdf;
+mod bvh;
 mod distribution_factors;
+mod emissivity;
+mod groups;
+mod meshes;
+mod radks;
+mod reciprocity;
+mod reproducibility;
+mod sampling;
+mod sides;
+mod thermal;
+mod transforms;
+mod uncertainty;
+#[allow(
+    clippy::empty_line_after_doc_comments,
+    clippy::empty_line_after_outer_attr
+)]
 mod view_factors;
 
 pub use distribution_factors::*;
+pub use meshes::*;
 pub use view_factors::*;
@@ -0,0 +1,60 @@
+#![cfg(test)]
+
+use crate::{surfaces::Surface, RadkOptions, Scene};
+
+use glam::Vec3;
+
+#[test]
+fn infinite_plates_conductance() {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(18));
+
+    let (e1, e2) = (0.2, 0.7);
+    let length = 10_000.;
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::ZERO, length, length, Vec3::Z, Vec3::X).to_gray_body(e1),
+    );
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::Z, length, length, Vec3::NEG_Z, Vec3::NEG_X).to_gray_body(e2),
+    );
+
+    // Gray plates exchange through an effective emissivity of 1 / (1/e1 + 1/e2 - 1)
+    let radks = scene.radks(&scene.view_factor_matrix(200_000), RadkOptions::default());
+    let expected = length * length / (1. / e1 + 1. / e2 - 1.);
+    assert!((radks[0][1] / expected - 1.).abs() < 0.01);
+    assert!((radks[1][0] / expected - 1.).abs() < 0.01);
+}
+
+#[test]
+fn symmetrized_and_thresholded() {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(19));
+
+    // Two facing disks with a small one off to the side
+    scene.add_surface(Surface::new_disk(Vec3::ZERO, 1., Vec3::Z).to_gray_body(0.8));
+    scene.add_surface(Surface::new_disk(Vec3::Z, 2., Vec3::NEG_Z).to_gray_body(0.5));
+    scene.add_surface(Surface::new_disk(Vec3::new(8., 0., 0.5), 0.4, Vec3::NEG_X));
+
+    let matrix = scene.view_factor_matrix(100_000);
+    let raw = scene.radks(&matrix, RadkOptions::default());
+    assert!(raw[0][2] > 0. && raw[0][1] != raw[1][0]);
+
+    let radks = scene.radks(
+        &matrix,
+        RadkOptions {
+            threshold: 0.01,
+            symmetrize: true,
+        },
+    );
+    for (i, row) in radks.iter().enumerate() {
+        for (j, radk) in row.iter().enumerate() {
+            assert_eq!(*radk, radks[j][i]);
+        }
+    }
+    assert!((radks[0][1] / (0.5 * (raw[0][1] + raw[1][0])) - 1.).abs() < 1e-6);
+
+    // The small disk barely exchanges with the others, relative to any of their emission
+    assert!(radks[0][1] > 0.);
+    assert_eq!(radks[0][2], 0.);
+    assert_eq!(radks[1][2], 0.);
+}
@@ -0,0 +1,80 @@
+#![cfg(test)]
+
+use crate::{correct_view_factors, surfaces::Surface, Imbalance, ReciprocityCorrection, Scene};
+
+use glam::Vec3;
+
+// Closed box with unequal faces, all facing inward
+fn box_enclosure(size: Vec3) -> Scene {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(5));
+
+    for (axis, axis_x) in [(Vec3::X, Vec3::Y), (Vec3::Y, Vec3::Z), (Vec3::Z, Vec3::X)] {
+        let width = axis_x.dot(size);
+        let height = axis.cross(axis_x).dot(size);
+        let offset = 0.5 * axis.dot(size) * axis;
+
+        scene.add_surface(Surface::new_rectangle(offset, width, height, -axis, axis_x));
+        scene.add_surface(Surface::new_rectangle(-offset, width, height, axis, axis_x));
+    }
+
+    scene
+}
+
+#[test]
+fn corrected_enclosure_is_reciprocal_and_closed() {
+    let scene = box_enclosure(Vec3::new(1., 2., 3.));
+    let areas = scene.areas();
+    let matrix = scene.view_factor_matrix(20_000);
+
+    let corrections = [
+        ReciprocityCorrection::LeastSquares,
+        ReciprocityCorrection::IterativeScaling {
+            tolerance: 1e-7,
+            max_iterations: 1000,
+        },
+    ];
+
+    for correction in corrections {
+        let corrected = correct_view_factors(&matrix, &areas, true, correction);
+
+        // Raw estimates are never exactly reciprocal
+        assert!(corrected.imbalance.max_reciprocity_error > 1e-3);
+
+        let imbalance = Imbalance::of(&corrected.view_factors, &areas);
+        assert!(imbalance.max_reciprocity_error < 1e-5, "{imbalance:?}");
+        assert!(imbalance.max_closure_error < 1e-5, "{imbalance:?}");
+
+        // Corrections stay within the Monte Carlo noise, and faces still cannot see themselves
+        for (row, corrected_row) in matrix.iter().zip(&corrected.view_factors) {
+            for (estimate, &corrected) in row.estimates.iter().zip(corrected_row) {
+                assert!((estimate.mean - corrected).abs() < 0.01);
+                if estimate.mean == 0.0 {
+                    assert_eq!(corrected, 0.0);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn open_scene_is_only_made_reciprocal() {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(3));
+    scene.add_surface(Surface::new_disk(Vec3::X, 1., Vec3::NEG_X));
+    scene.add_surface(Surface::new_disk(Vec3::ZERO, 2., Vec3::X));
+    let areas = scene.areas();
+
+    let matrix = scene.view_factor_matrix(50_000);
+    let corrected =
+        correct_view_factors(&matrix, &areas, false, ReciprocityCorrection::LeastSquares);
+
+    let imbalance = Imbalance::of(&corrected.view_factors, &areas);
+    assert!(imbalance.max_reciprocity_error < 1e-5);
+
+    // The reported imbalance is that of the raw estimates
+    let raw: Vec<Vec<f32>> = matrix.iter().map(|row| row.means()).collect();
+    assert_eq!(corrected.imbalance, Imbalance::of(&raw, &areas));
+    assert!(corrected.imbalance.max_reciprocity_error > 1e-3);
+    assert!(corrected.view_factors[1][0] < 0.5);
+}
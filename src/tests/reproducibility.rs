@@ -0,0 +1,80 @@
+#![cfg(test)]
+
+use crate::{
+    primitives::Cylinder, surfaces::Surface, ConvergenceTarget, Scene, SurfaceId, ViewFactors,
+};
+
+use glam::Vec3;
+
+// Gray diffuse and specular surfaces so rays take varying numbers of bounces
+fn seeded_scene(seed: u64) -> Scene {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(seed));
+
+    scene.add_surface(Surface::new_disk(Vec3::ZERO, 1., Vec3::Z).to_gray_body(0.3));
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::Z, 2., 2., Vec3::NEG_Z, Vec3::X)
+            .to_gray_body(0.5)
+            .set_specular(),
+    );
+    scene.add_primitive(Cylinder {
+        translation: Vec3::new(0., 0., 0.5),
+        axis: Vec3::X,
+        height: 0.5,
+        radius: 0.2,
+    });
+
+    scene
+}
+
+fn bits<'a>(rows: impl IntoIterator<Item = &'a ViewFactors>) -> Vec<(u32, u32)> {
+    rows.into_iter()
+        .flat_map(|row| &row.estimates)
+        .map(|estimate| (estimate.mean.to_bits(), estimate.variance.to_bits()))
+        .collect()
+}
+
+fn with_threads<T: Send>(num_threads: usize, f: impl FnOnce() -> T + Send) -> T {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build()
+        .unwrap()
+        .install(f)
+}
+
+#[test]
+fn seeded_matrix_independent_of_thread_count() {
+    let scene = seeded_scene(7);
+
+    let single = with_threads(1, || scene.view_factor_matrix(25_000));
+    let many = with_threads(5, || scene.view_factor_matrix(25_000));
+    assert_eq!(bits(&single), bits(&many));
+
+    let target = ConvergenceTarget {
+        relative_standard_error: 0.02,
+        min_view_factor: 0.05,
+        max_rays: 200_000,
+    };
+    let single = with_threads(1, || scene.view_factor_matrix_adaptive(&target));
+    let many = with_threads(3, || scene.view_factor_matrix_adaptive(&target));
+    assert_eq!(bits(&single), bits(&many));
+}
+
+#[test]
+fn seeded_serial_matches_parallel() {
+    let scene = seeded_scene(11);
+
+    let serial = scene.view_factors_for_surface(SurfaceId(0), 35_000);
+    let parallel = scene.view_factors_for_surface_parallel(SurfaceId(0), 35_000);
+    assert_eq!(bits([&serial]), bits([&parallel]));
+
+    // Running again reproduces the result, and a different seed does not
+    assert_eq!(
+        bits([&serial]),
+        bits([&scene.view_factors_for_surface(SurfaceId(0), 35_000)])
+    );
+    assert_ne!(
+        bits([&serial]),
+        bits([&seeded_scene(12).view_factors_for_surface(SurfaceId(0), 35_000)])
+    );
+}
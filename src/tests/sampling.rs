@@ -0,0 +1,132 @@
+#![cfg(test)]
+
+use crate::{
+    sampling::RaySampler, surfaces::Surface, tests::equal_rectangular_plates, ConvergenceTarget,
+    Sampling, Scene, SurfaceId,
+};
+
+use glam::Vec3;
+use rand::RngCore;
+
+#[test]
+fn sobol_dimensions_are_stratified() {
+    const NUM_POINTS: usize = 256;
+
+    for owen_scrambling in [false, true] {
+        let sampling = Sampling::Sobol { owen_scrambling };
+        let mut sampler = RaySampler::new(sampling, 3, 0, 0);
+        let num_replicates = sampling.num_replicates();
+
+        for replicate in 0..num_replicates {
+            for dimension in 0..16 {
+                // Every one of the equal intervals gets exactly one of the first points of each
+                // replicate sequence
+                let mut counts = [0; NUM_POINTS];
+                for index in 0..NUM_POINTS {
+                    sampler.start_ray(index * num_replicates + replicate);
+                    sampler.start_dimension(dimension);
+                    counts[(sampler.next_u32() >> 24) as usize] += 1;
+                }
+                assert!(counts.iter().all(|&count| count == 1), "{dimension}");
+            }
+        }
+    }
+}
+
+#[test]
+fn scrambled_sobol_beats_pseudo_random() {
+    let (analytic, _) = equal_rectangular_plates(1.0, 1.0);
+
+    let rms_error = |sampling: Sampling| {
+        let mut scene = Scene::default();
+        scene.set_sampling(sampling);
+        scene.add_surface(Surface::new_rectangle(Vec3::X, 1., 1., -Vec3::X, Vec3::Z));
+        scene.add_surface(Surface::new_rectangle(Vec3::ZERO, 1., 1., Vec3::X, Vec3::Z));
+
+        let squared_errors: f32 = (0..32)
+            .map(|seed| {
+                scene.set_seed(Some(seed));
+                let v = scene.view_factors_for_surface_parallel(SurfaceId(0), 1 << 16)[1].mean;
+                (v - analytic).powi(2)
+            })
+            .sum();
+
+        (squared_errors / 32.).sqrt()
+    };
+
+    let pseudo_random = rms_error(Sampling::PseudoRandom);
+    let sobol = rms_error(Sampling::Sobol {
+        owen_scrambling: true,
+    });
+
+    assert!(sobol < 0.5 * pseudo_random, "{sobol} vs {pseudo_random}");
+}
+
+#[test]
+fn scrambled_sobol_standard_errors() {
+    let (analytic, _) = equal_rectangular_plates(1.0, 1.0);
+
+    let mut scene = Scene::default();
+    scene.set_sampling(Sampling::Sobol {
+        owen_scrambling: true,
+    });
+    scene.add_surface(Surface::new_rectangle(Vec3::X, 1., 1., -Vec3::X, Vec3::Z));
+    scene.add_surface(Surface::new_rectangle(Vec3::ZERO, 1., 1., Vec3::X, Vec3::Z));
+
+    // Errors in units of the reported standard error should have a spread of about one
+    let num_seeds = 32;
+    let (mut squared_z, mut iid_ratio) = (0., 0.);
+    for seed in 0..num_seeds {
+        scene.set_seed(Some(seed));
+        let estimate = scene.view_factors_for_surface_parallel(SurfaceId(0), 1 << 16)[1].clone();
+        squared_z += ((estimate.mean - analytic) / estimate.standard_error).powi(2);
+        iid_ratio += estimate.standard_error / (estimate.variance / (1 << 16) as f32).sqrt();
+    }
+    let rms_z = (squared_z / num_seeds as f32).sqrt();
+    assert!(rms_z > 0.5 && rms_z < 2., "{rms_z}");
+
+    // Treating the points as independent would overstate the error
+    assert!(iid_ratio / (num_seeds as f32) < 0.5);
+}
+
+#[test]
+fn scrambled_sobol_intervals_use_replicates() {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(3));
+    scene.set_sampling(Sampling::Sobol {
+        owen_scrambling: true,
+    });
+    scene.add_surface(Surface::new_rectangle(Vec3::X, 1., 1., -Vec3::X, Vec3::Z));
+    scene.add_surface(Surface::new_rectangle(Vec3::ZERO, 1., 1., Vec3::X, Vec3::Z));
+
+    // The standard error comes from eight replicate means, so the interval is a Student-t one
+    // with seven degrees of freedom, 2.365 standard errors wide on each side instead of 1.960
+    let estimate = scene.view_factors_for_surface(SurfaceId(0), 1 << 12)[1].clone();
+    assert_eq!(estimate.degrees_of_freedom, 7);
+    let (low, high) = estimate.confidence_interval(0.95);
+    let half_width = 0.5 * (high - low) / estimate.standard_error;
+    assert!((half_width - 2.364624).abs() < 1e-4, "{half_width}");
+
+    // Independent rays have one less degree of freedom than rays
+    scene.set_sampling(Sampling::PseudoRandom);
+    let estimate = scene.view_factors_for_surface(SurfaceId(0), 1 << 12)[1].clone();
+    assert_eq!(estimate.degrees_of_freedom, (1 << 12) - 1);
+}
+
+#[test]
+#[should_panic(expected = "adaptive ray counts need")]
+fn unscrambled_sobol_is_not_adaptive() {
+    let mut scene = Scene::default();
+    scene.set_sampling(Sampling::Sobol {
+        owen_scrambling: false,
+    });
+    scene.add_surface(Surface::new_rectangle(Vec3::X, 1., 1., -Vec3::X, Vec3::Z));
+    scene.view_factors_for_surface_adaptive(
+        SurfaceId(0),
+        &ConvergenceTarget {
+            relative_standard_error: 0.01,
+            min_view_factor: 0.01,
+            max_rays: 100_000,
+        },
+    );
+}
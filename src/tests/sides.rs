@@ -0,0 +1,132 @@
+#![cfg(test)]
+
+use crate::{
+    surfaces::{ActiveSides, Material, Side, Surface},
+    tests::equal_rectangular_plates,
+    Point, Ray, Scene, SurfaceId,
+};
+
+use glam::Vec3;
+
+// Square panel between two equal squares, one unit away on either side and facing it
+fn panel_between_plates(panel: Surface) -> Scene {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(1));
+
+    scene.add_surface(panel);
+    scene.add_surface(Surface::new_rectangle(
+        Vec3::Z,
+        1.,
+        1.,
+        Vec3::NEG_Z,
+        Vec3::X,
+    ));
+    scene.add_surface(Surface::new_rectangle(
+        Vec3::NEG_Z,
+        1.,
+        1.,
+        Vec3::Z,
+        Vec3::X,
+    ));
+
+    scene
+}
+
+#[test]
+fn two_sided_panel_radiates_from_both_faces() {
+    let (analytic, _) = equal_rectangular_plates(1., 1.);
+    let panel = Surface::new_rectangle(Vec3::ZERO, 1., 1., Vec3::Z, Vec3::X);
+
+    // A one sided panel only sees the plate in front of it
+    let view_factors =
+        panel_between_plates(panel.clone()).view_factors_for_surface(SurfaceId(0), 400_000);
+    assert!((view_factors[1].mean / analytic - 1.).abs() < 0.01);
+    assert_eq!(view_factors[2].mean, 0.);
+
+    // Both sides emit half of the rays when they have the same emissivity
+    let two_sided = panel.clone().set_active_sides(ActiveSides::Both);
+    let view_factors =
+        panel_between_plates(two_sided).view_factors_for_surface(SurfaceId(0), 400_000);
+    for plate in [1, 2] {
+        assert!((view_factors[plate].mean / (0.5 * analytic) - 1.).abs() < 0.01);
+    }
+
+    // Otherwise each side emits in proportion to its emissivity
+    let uneven = panel
+        .set_active_sides(ActiveSides::Both)
+        .set_side_optics(Side::Front, 0.8, Material::Diffuse)
+        .set_side_optics(Side::Back, 0.2, Material::Diffuse);
+    let view_factors = panel_between_plates(uneven).view_factors_for_surface(SurfaceId(0), 400_000);
+    assert!((view_factors[1].mean / (0.8 * analytic) - 1.).abs() < 0.02);
+    assert!((view_factors[2].mean / (0.2 * analytic) - 1.).abs() < 0.04);
+}
+
+#[test]
+fn hit_records_report_side() {
+    let mut scene = Scene::default();
+    scene.add_surface(Surface::new_disk(Vec3::ZERO, 1., Vec3::Z));
+    scene.add_surface(Surface::new_sphere(5. * Vec3::X, 1.));
+
+    let cast = |origin: Vec3, direction: Vec3| {
+        scene
+            .cast_ray(&Ray {
+                origin: Point(origin),
+                direction,
+            })
+            .unwrap()
+    };
+
+    assert_eq!(cast(Vec3::Z, Vec3::NEG_Z).side, Side::Front);
+    assert_eq!(cast(Vec3::NEG_Z, Vec3::Z).side, Side::Back);
+    assert_eq!(cast(Vec3::ZERO + 2. * Vec3::X, Vec3::X).side, Side::Front);
+    assert_eq!(cast(5. * Vec3::X, Vec3::X).side, Side::Back);
+
+    // The facing normal always points back along the ray
+    let hit = cast(Vec3::NEG_Z, Vec3::Z);
+    assert_eq!(hit.facing_normal().vec(), Vec3::NEG_Z);
+}
+
+#[test]
+fn back_sides_reflect_and_block_correctly() {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(2));
+
+    // Emitter below a mirror whose back side faces it, with receivers above and below
+    scene.add_surface(Surface::new_disk(Vec3::NEG_Z, 0.1, Vec3::Z));
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::ZERO, 100., 100., Vec3::Z, Vec3::X)
+            .set_active_sides(ActiveSides::Both)
+            .set_side_optics(Side::Back, 0., Material::Specular),
+    );
+    scene.add_surface(Surface::new_rectangle(
+        Vec3::Z,
+        100.,
+        100.,
+        Vec3::NEG_Z,
+        Vec3::X,
+    ));
+    scene.add_surface(Surface::new_rectangle(
+        -2. * Vec3::Z,
+        100.,
+        100.,
+        Vec3::Z,
+        Vec3::X,
+    ));
+
+    // The mirror sends everything back down instead of through itself
+    let view_factors = scene.view_factors_for_surface(SurfaceId(0), 100_000);
+    assert_eq!(view_factors[2].mean, 0.);
+    assert!(view_factors[3].mean > 0.95);
+
+    // Rays from inside a sphere hit the back of its outward facing surface. By default they are
+    // absorbed there, but an inactive back loses them.
+    let enclosed = |sphere: Surface| {
+        let mut scene = Scene::default();
+        scene.add_surface(Surface::new_disk(Vec3::ZERO, 0.1, Vec3::Z));
+        scene.add_surface(sphere);
+        scene.view_factors_for_surface(SurfaceId(0), 10_000)[1].mean
+    };
+    assert!(enclosed(Surface::new_sphere(Vec3::ZERO, 1.)) > 0.999);
+    let front_only = Surface::new_sphere(Vec3::ZERO, 1.).set_active_sides(ActiveSides::Front);
+    assert_eq!(enclosed(front_only), 0.);
+}
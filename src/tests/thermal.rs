@@ -0,0 +1,277 @@
+#![cfg(test)]
+
+use crate::{
+    surfaces::{ActiveSides, Surface},
+    Integrator, RadkOptions, Scene, SolverOptions, ThermalError, ThermalNetwork, TransientOptions,
+    STEFAN_BOLTZMANN,
+};
+
+use glam::Vec3;
+
+#[test]
+fn infinite_plates_steady_state() {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(20));
+
+    let (e1, e2) = (0.2, 0.7);
+    let length = 10_000.;
+    let area = length * length;
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::ZERO, length, length, Vec3::Z, Vec3::X).to_gray_body(e1),
+    );
+    scene.add_surface(
+        Surface::new_rectangle(Vec3::Z, length, length, Vec3::NEG_Z, Vec3::NEG_X).to_gray_body(e2),
+    );
+
+    let options = RadkOptions {
+        threshold: 0.,
+        symmetrize: true,
+    };
+    let radks = scene.radks(&scene.view_factor_matrix(200_000), options);
+    let mut network = ThermalNetwork::new(&radks);
+
+    // The lower plate dissipates 100 W/m^2 into the upper one, which is held at 300 K
+    let flux = 100.;
+    network.set_heat_load(0, flux * area);
+    network.set_boundary_temperature(1, Some(300.));
+    let steady_state = network
+        .solve_steady_state(SolverOptions::default())
+        .unwrap();
+
+    let t1 = (300f32.powi(4) + flux * (1. / e1 + 1. / e2 - 1.) / STEFAN_BOLTZMANN).powf(0.25);
+    assert!((steady_state.temperatures[0] - t1).abs() < 0.5);
+    assert_eq!(steady_state.temperatures[1], 300.);
+    assert!((steady_state.boundary_heat_flows[1] / (flux * area) + 1.).abs() < 0.01);
+}
+
+#[test]
+fn radiation_shield_between_groups() {
+    let mut scene = Scene::default();
+    scene.set_seed(Some(21));
+
+    // Hot and cold plates with a two-sided shield halfway between them, made of two halves
+    let length = 10_000.;
+    let (e_plates, e_shield) = (0.8, 0.1);
+    let hot = scene.add_surface(
+        Surface::new_rectangle(Vec3::ZERO, length, length, Vec3::Z, Vec3::X).to_gray_body(e_plates),
+    );
+    let shield = [-0.25, 0.25].map(|offset| {
+        scene.add_surface(
+            Surface::new_rectangle(
+                Vec3::new(offset * length, 0., 1.),
+                0.5 * length,
+                length,
+                Vec3::Z,
+                Vec3::X,
+            )
+            .set_active_sides(ActiveSides::Both)
+            .to_gray_body(e_shield),
+        )
+    });
+    let cold = scene.add_surface(
+        Surface::new_rectangle(2. * Vec3::Z, length, length, Vec3::NEG_Z, Vec3::X)
+            .to_gray_body(e_plates),
+    );
+    scene.add_group("hot", &[hot]);
+    scene.add_group("shield", &shield);
+    scene.add_group("cold", &[cold]);
+
+    let radks = scene.radks(
+        &scene.view_factor_matrix(200_000),
+        RadkOptions {
+            threshold: 0.,
+            symmetrize: true,
+        },
+    );
+    let mut network = ThermalNetwork::new(&scene.group_radks(&radks));
+    network.set_boundary_temperature(0, Some(500.));
+    network.set_boundary_temperature(2, Some(300.));
+    let steady_state = network
+        .solve_steady_state(SolverOptions::default())
+        .unwrap();
+
+    // Both gaps have the same resistance, so the shield sits at the mean of T^4
+    let t_shield = (0.5 * (500f32.powi(4) + 300f32.powi(4))).powf(0.25);
+    assert!((steady_state.temperatures[1] - t_shield).abs() < 0.5);
+
+    let resistance = 1. / e_plates + 1. / e_shield - 1.;
+    let flux = STEFAN_BOLTZMANN * (500f32.powi(4) - t_shield.powi(4)) / resistance;
+    let hot_flow = steady_state.boundary_heat_flows[0];
+    assert!((hot_flow / (flux * length * length) - 1.).abs() < 0.01);
+}
+
+#[test]
+fn conductors_and_space() {
+    // A node radiating to 3 K space and conducting to a second node held at 250 K
+    let mut network = ThermalNetwork::new(&[vec![0., 0.], vec![0., 0.]]);
+    network.set_space(&[0.5, 0.], 3.);
+    network.set_boundary_temperature(1, Some(250.));
+    network.set_heat_load(0, 200.);
+    network.add_conductor(0, 1, 0.1);
+
+    let steady_state = network
+        .solve_steady_state(SolverOptions::default())
+        .unwrap();
+    let t = steady_state.temperatures[0];
+    let balance = 200. - STEFAN_BOLTZMANN * 0.5 * (t.powi(4) - 3f32.powi(4)) - 0.1 * (t - 250.);
+    assert!(balance.abs() < 1e-3);
+
+    // Whatever the conductor carries goes into the boundary node
+    assert!((steady_state.boundary_heat_flows[1] + 0.1 * (t - 250.)).abs() < 1e-3);
+}
+
+#[test]
+fn radiative_cooldown() {
+    // A lumped node radiating to 0 K space, T(t) = (T0^-3 + 3 sigma e A t / C)^(-1/3)
+    let (emitting_area, capacitance, t0) = (0.5, 1000., 400f32);
+    let mut network = ThermalNetwork::new(&[vec![0.]]);
+    network.set_space(&[emitting_area], 0.);
+    network.set_capacitance(0, capacitance);
+
+    let duration = 3600.;
+    let exact = (t0.powi(-3) + 3. * STEFAN_BOLTZMANN * emitting_area * duration / capacitance)
+        .powf(-1. / 3.);
+
+    let errors = [Integrator::ForwardEuler, Integrator::CrankNicolson].map(|integrator| {
+        let history = network
+            .solve_transient(&[t0], TransientOptions::new(integrator, 60., duration))
+            .unwrap();
+        assert_eq!(history.times.len(), 61);
+        assert_eq!(*history.times.last().unwrap(), duration);
+        (history.temperatures.last().unwrap()[0] - exact).abs()
+    });
+
+    assert!(errors[0] < 3.);
+    assert!(errors[1] < 0.2);
+    assert!(errors[1] < errors[0]);
+}
+
+#[test]
+fn node_follows_boundary_ramp() {
+    // A node conducting to a boundary that heats up at k K/s lags it by the time constant C / G
+    let mut network = ThermalNetwork::new(&[vec![0., 0.], vec![0., 0.]]);
+    let (capacitance, conductance, k) = (50., 0.5, 0.1);
+    network.set_capacitance(0, capacitance);
+    network.add_conductor(0, 1, conductance);
+    network.set_boundary_temperature_schedule(1, &[(0., 300.), (1000., 300. + 1000. * k)]);
+
+    let history = network
+        .solve_transient(
+            &[300., 0.],
+            TransientOptions::new(Integrator::CrankNicolson, 1., 500.),
+        )
+        .unwrap();
+
+    let tau = capacitance / conductance;
+    for (&time, temperatures) in history.times.iter().zip(&history.temperatures) {
+        let exact = 300. + k * (time - tau * (1. - (-time / tau).exp()));
+        assert!((temperatures[0] - exact).abs() < 0.01);
+        assert!((temperatures[1] - (300. + k * time)).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn transient_settles_to_steady_state() {
+    let mut network = ThermalNetwork::new(&[vec![0., 0.2], vec![0.2, 0.]]);
+    network.set_space(&[0.5, 0.5], 3.);
+    network.set_heat_load(0, 150.);
+    network.set_boundary_temperature(1, Some(250.));
+    network.set_capacitance(0, 200.);
+
+    let steady_state = network
+        .solve_steady_state(SolverOptions::default())
+        .unwrap();
+    let history = network
+        .solve_transient(
+            &[100., 100.],
+            TransientOptions::new(Integrator::CrankNicolson, 30., 20_000.),
+        )
+        .unwrap();
+
+    // Boundary temperatures replace the initial ones
+    assert_eq!(history.temperatures[0], vec![100., 250.]);
+    let last = history.temperatures.last().unwrap();
+    assert!((last[0] - steady_state.temperatures[0]).abs() < 0.01);
+}
+
+#[test]
+#[should_panic(expected = "needs a capacitance")]
+fn transient_needs_capacitances() {
+    let network = ThermalNetwork::new(&[vec![0.]]);
+    let _ = network.solve_transient(
+        &[300.],
+        TransientOptions::new(Integrator::ForwardEuler, 1., 10.),
+    );
+}
+
+#[test]
+fn nodes_need_a_heat_path() {
+    // A loaded node coupled to nothing has no temperature that balances it
+    let mut network = ThermalNetwork::new(&[vec![0.]]);
+    network.set_heat_load(0, 100.);
+    assert!(matches!(
+        network.solve_steady_state(SolverOptions::default()),
+        Err(ThermalError::NoHeatPath { node: 0 })
+    ));
+
+    // Neither does a pair of nodes that only exchange heat with each other
+    let mut network =
+        ThermalNetwork::new(&[vec![0., 0.1, 0.], vec![0.1, 0., 0.], vec![0., 0., 0.]]);
+    network.set_space(&[0., 0., 1.], 3.);
+    network.add_conductor(0, 1, 0.5);
+    network.set_heat_load(0, 10.);
+    assert!(matches!(
+        network.solve_steady_state(SolverOptions::default()),
+        Err(ThermalError::NoHeatPath { node: 0 | 1 })
+    ));
+}
+
+#[test]
+#[should_panic(expected = "Initial temperature must be positive")]
+fn steady_state_starts_above_zero() {
+    let mut network = ThermalNetwork::new(&[vec![0.]]);
+    network.set_space(&[0.5], 3.);
+    let _ = network.solve_steady_state(SolverOptions {
+        initial_temperature: 0.,
+        ..SolverOptions::default()
+    });
+}
+
+#[test]
+fn forward_euler_steps_are_checked() {
+    // The radiating node's time constant C / (4 sigma e A T^3) is 138 s at 400 K
+    let mut network = ThermalNetwork::new(&[vec![0.]]);
+    network.set_space(&[0.5], 0.);
+    network.set_capacitance(0, 1000.);
+    let history = network.solve_transient(
+        &[400.],
+        TransientOptions::new(Integrator::ForwardEuler, 200., 1000.),
+    );
+    assert!(matches!(
+        history,
+        Err(ThermalError::UnstableTimeStep { node: 0, max_time_step, .. })
+            if (max_time_step - 137.8).abs() < 0.1
+    ));
+
+    // Implicit steps of any length are fine
+    let history = network.solve_transient(
+        &[400.],
+        TransientOptions::new(Integrator::CrankNicolson, 200., 1000.),
+    );
+    assert!(history.is_ok());
+
+    // A node cooled faster than its slow conductor can make up for drops below 0 K
+    let mut network = ThermalNetwork::new(&[vec![0., 0.], vec![0., 0.]]);
+    network.set_capacitance(0, 10.);
+    network.add_conductor(0, 1, 0.01);
+    network.set_boundary_temperature(1, Some(20.));
+    network.set_heat_load(0, -200.);
+    let history = network.solve_transient(
+        &[10., 20.],
+        TransientOptions::new(Integrator::ForwardEuler, 1., 10.),
+    );
+    assert!(matches!(
+        history,
+        Err(ThermalError::NegativeTemperature { node: 0, time }) if time == 1.
+    ));
+}
@@ -0,0 +1,37 @@
+#![cfg(test)]
+
+use crate::{surfaces::Surface, transform::Transform, Normal};
+
+use glam::Vec3;
+
+// Every axis aligned normal with every in-plane x axis, including x axes that start out
+// antiparallel to where the first rotation takes local x
+#[test]
+fn rotations_keep_requested_axes() {
+    let axes = [
+        Vec3::X,
+        Vec3::NEG_X,
+        Vec3::Y,
+        Vec3::NEG_Y,
+        Vec3::Z,
+        Vec3::NEG_Z,
+    ];
+
+    for axis_z in axes {
+        for axis_x in axes.into_iter().filter(|axis| axis.dot(axis_z) == 0.) {
+            let transform = Transform::new(Vec3::ZERO, Vec3::ONE, axis_z, axis_x);
+
+            let normal = transform.normal_local_to_world(Normal::new(Vec3::Z)).0;
+            assert!(normal.abs_diff_eq(axis_z, 1e-5), "{axis_z} {axis_x}");
+            let x = transform.vec_local_to_world(Vec3::X);
+            assert!(x.abs_diff_eq(axis_x, 1e-5), "{axis_z} {axis_x}");
+        }
+    }
+}
+
+#[test]
+fn downward_rectangle_faces_down() {
+    let rectangle = Surface::new_rectangle(Vec3::ZERO, 1., 1., Vec3::NEG_Z, Vec3::X);
+    let sample = rectangle.sample(&mut rand::thread_rng());
+    assert!(sample.normal.vec().abs_diff_eq(Vec3::NEG_Z, 1e-5));
+}
@@ -0,0 +1,129 @@
+#![cfg(test)]
+
+use crate::{surfaces::Surface, ConvergenceTarget, Scene, SurfaceId, ViewFactorEstimate};
+
+use glam::Vec3;
+
+#[test]
+fn confidence_interval_contains_analytical() {
+    let mut scene = Scene::default();
+    scene.add_surface(Surface::new_disk(Vec3::X, 1., Vec3::NEG_X));
+    scene.add_surface(Surface::new_disk(Vec3::ZERO, 2., Vec3::X));
+
+    let view_factors = scene.view_factors_for_surface_parallel(SurfaceId(0), 100_000);
+    let (v_an, _) = super::unequal_disks(1.0, 2.0);
+
+    // Misses by chance with probability 1e-5
+    let (low, high) = view_factors[1].confidence_interval(0.99999);
+    assert!(low < v_an && v_an < high);
+
+    // Black surfaces absorb all or nothing from a ray, so the variance is Bernoulli
+    let mean = view_factors[1].mean;
+    assert!((view_factors[1].variance - mean * (1. - mean)).abs() < 1e-4);
+    assert_eq!(view_factors.num_rays, 100_000);
+}
+
+#[test]
+fn confidence_interval_width() {
+    let estimate = ViewFactorEstimate {
+        mean: 0.5,
+        variance: 1.,
+        standard_error: 1.,
+        degrees_of_freedom: 1_000_000,
+    };
+
+    let (low, high) = estimate.confidence_interval(0.95);
+    assert!((high - 0.5 - 1.959964).abs() < 1e-5);
+    assert!((0.5 - low - 1.959964).abs() < 1e-5);
+
+    let (low, high) = estimate.confidence_interval(0.999);
+    assert!((high - low - 2. * 3.290527).abs() < 1e-4);
+}
+
+#[test]
+fn student_t_interval_width() {
+    // Tabulated two sided Student-t quantiles
+    for (degrees_of_freedom, confidence, quantile) in [
+        (1, 0.95, 12.7062),
+        (2, 0.95, 4.302653),
+        (7, 0.95, 2.364624),
+        (7, 0.99, 3.499483),
+        (30, 0.95, 2.042272),
+    ] {
+        let estimate = ViewFactorEstimate {
+            mean: 0.5,
+            variance: 1.,
+            standard_error: 1.,
+            degrees_of_freedom,
+        };
+        let (low, high) = estimate.confidence_interval(confidence);
+        assert!(
+            (0.5 * (high - low) / quantile - 1.).abs() < 1e-5,
+            "{degrees_of_freedom} {confidence}"
+        );
+    }
+}
+
+#[test]
+fn adaptive_view_factor_matrix() {
+    let mut scene = Scene::default();
+    scene.add_surface(Surface::new_disk(Vec3::X, 1., Vec3::NEG_X));
+    scene.add_surface(Surface::new_disk(Vec3::ZERO, 2., Vec3::X));
+
+    let target = ConvergenceTarget {
+        relative_standard_error: 0.005,
+        min_view_factor: 0.01,
+        max_rays: 10_000_000,
+    };
+    let matrix = scene.view_factor_matrix_adaptive(&target);
+
+    for row in &matrix {
+        assert!(target.is_met(row));
+        assert!(row.num_rays < target.max_rays);
+    }
+
+    // The large disk sees the small one poorly, so it needs more rays to converge
+    assert!(matrix[1].num_rays > matrix[0].num_rays);
+
+    let (v_an, _) = super::unequal_disks(1.0, 2.0);
+    assert!((matrix[0][1].mean / v_an - 1.).abs() < 0.02);
+
+    // Stops at the ray budget when the target cannot be met
+    let budget = ConvergenceTarget {
+        relative_standard_error: 1e-6,
+        min_view_factor: 0.01,
+        max_rays: 25_000,
+    };
+    assert_eq!(
+        scene
+            .view_factors_for_surface_adaptive(SurfaceId(1), &budget)
+            .num_rays,
+        25_000
+    );
+}
+
+#[test]
+fn unseen_surfaces_need_not_converge() {
+    let mut scene = Scene::default();
+    scene.add_surface(Surface::new_disk(Vec3::X, 1., Vec3::NEG_X));
+    scene.add_surface(Surface::new_disk(Vec3::ZERO, 2., Vec3::X));
+
+    // The disk never sees itself, which must not hold up every other view factor
+    let target = ConvergenceTarget {
+        relative_standard_error: 0.01,
+        min_view_factor: 0.,
+        max_rays: 10_000_000,
+    };
+    let view_factors = scene.view_factors_for_surface_adaptive(SurfaceId(0), &target);
+    assert_eq!(view_factors[0].mean, 0.);
+    assert!(target.is_met(&view_factors));
+    assert!(view_factors.num_rays < target.max_rays);
+}
+
+#[test]
+#[should_panic(expected = "num_rays_per_surface > 0")]
+fn view_factors_need_rays() {
+    let mut scene = Scene::default();
+    scene.add_surface(Surface::new_disk(Vec3::ZERO, 1., Vec3::X));
+    scene.view_factor_matrix(0);
+}
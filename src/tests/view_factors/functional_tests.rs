@@ -59,3 +59,38 @@ fn sphere_to_coaxial_cone() {
 fn coaxial_cone_to_sphere() {
     assert!(percent_error(super::coaxial_cone_to_sphere(2.0, 1.0, 40_f32.to_radians())) < 1.)
 }
+
+#[test]
+fn rectangle_to_triangulated_plate() {
+    assert!(percent_error(super::rectangle_to_triangulated_plate(1.0, 2.0)).abs() < 1.)
+}
+
+#[test]
+fn cube_enclosure_opposite_faces() {
+    assert!(percent_error(super::cube_enclosure_opposite_faces()).abs() < 1.)
+}
+
+#[test]
+fn sphere_in_partial_cylinder() {
+    assert!(percent_error(super::sphere_in_partial_cylinder(1.0)).abs() < 1.)
+}
+
+#[test]
+fn pie_slice_to_coaxial_disk() {
+    assert!(percent_error(super::pie_slice_to_coaxial_disk(1.0, 2.0, 1.0)).abs() < 1.)
+}
+
+#[test]
+fn frustum_base_to_side() {
+    assert!(percent_error(super::frustum_base_to_side(0.5, 1.0)).abs() < 1.)
+}
+
+#[test]
+fn disk_to_coaxial_annulus() {
+    assert!(percent_error(super::disk_to_coaxial_annulus(1.0, 0.5, 1.5)).abs() < 1.)
+}
+
+#[test]
+fn sphere_in_hollow_cylinder() {
+    assert!(percent_error(super::sphere_in_hollow_cylinder(1.0)).abs() < 1.)
+}
@@ -1,6 +1,6 @@
 mod functional_tests;
 
-use crate::{surfaces::Surface, Scene};
+use crate::{primitives::HollowCylinder, surfaces::Surface, Scene, SurfaceId};
 
 use glam::f32::Vec3;
 
@@ -16,7 +16,7 @@ pub fn sphere_in_cylinder(h: f32) -> (f32, f32) {
 
     scene.add_surface(Surface::new_cylinder(Vec3::ZERO, 1.0, 2. * h, Vec3::Z));
 
-    let v = scene.view_factors_for_surface(0, 1_000_000)[1];
+    let v = scene.view_factors_for_surface(SurfaceId(0), 1_000_000)[1].mean;
     let v_an = h / (1.0f32 + h * h).sqrt();
 
     (v_an, v)
@@ -35,7 +35,7 @@ pub fn disk_to_sphere(h: f32, r: f32) -> (f32, f32) {
 
     scene.add_surface(Surface::new_sphere(Vec3::ZERO, r));
 
-    let v = scene.view_factors_for_surface(0, 1_000_000)[1];
+    let v = scene.view_factors_for_surface(SurfaceId(0), 1_000_000)[1].mean;
     let v_an = 2. * r * r * (1. - 1. / (1. + 1. / (h * h)).sqrt());
 
     (v_an, v)
@@ -57,7 +57,7 @@ pub fn small_cylinder_to_sphere(h: f32) -> (f32, f32) {
 
     scene.add_surface(Surface::new_sphere(Vec3::ZERO, 1.0));
 
-    let v = scene.view_factors_for_surface(0, 1_000_000)[1];
+    let v = scene.view_factors_for_surface(SurfaceId(0), 1_000_000)[1].mean;
 
     let s = (1. - 1. / (h * h)).sqrt();
     let pi = std::f32::consts::PI;
@@ -77,7 +77,7 @@ pub fn small_sphere_to_sphere(h: f32) -> (f32, f32) {
 
     scene.add_surface(Surface::new_sphere(Vec3::ZERO, 1.0));
 
-    let v = scene.view_factors_for_surface(0, 1_000_000)[1];
+    let v = scene.view_factors_for_surface(SurfaceId(0), 1_000_000)[1].mean;
     let v_an = 0.5 * (1. - (1. - 1. / (h * h)).sqrt());
 
     (v_an, v)
@@ -94,7 +94,7 @@ pub fn cylinder_to_cylinder(h: f32) -> (f32, f32) {
 
     scene.add_surface(Surface::new_cylinder(Vec3::X * h, 1.0, 10_000.0, Vec3::Y));
 
-    let v = scene.view_factors_for_surface(0, 1_000_000)[1];
+    let v = scene.view_factors_for_surface(SurfaceId(0), 1_000_000)[1].mean;
 
     let pi = std::f32::consts::PI;
     let v_an = ((h * h - 4.).sqrt() - h + 2. * (2. / h).asin()) / (2. * pi);
@@ -119,7 +119,7 @@ pub fn strip_to_cylinder(h: f32, v: f32) -> (f32, f32) {
 
     scene.add_surface(Surface::new_cylinder(Vec3::ZERO, 1.0, 10_000.0, Vec3::Y));
 
-    let f = scene.view_factors_for_surface(0, 1_000_000)[1];
+    let f = scene.view_factors_for_surface(SurfaceId(0), 1_000_000)[1].mean;
     let f_an = (v / h).atan() / v;
 
     (f_an, f)
@@ -134,7 +134,7 @@ pub fn rod_to_coaxial_disk(h: f32) -> (f32, f32) {
 
     scene.add_surface(Surface::new_disk(Vec3::ZERO, 1.0, Vec3::Z));
 
-    let v = scene.view_factors_for_surface(0, 1_000_000)[1];
+    let v = scene.view_factors_for_surface(SurfaceId(0), 1_000_000)[1].mean;
 
     let pi = std::f32::consts::PI;
     let v_an = 0.25 - 0.5 / pi * ((h * h - 1.) / (h * h + 1.)).asin();
@@ -152,7 +152,7 @@ pub fn equal_rectangular_plates(x: f32, y: f32) -> (f32, f32) {
 
     scene.add_surface(Surface::new_rectangle(Vec3::ZERO, x, y, Vec3::X, Vec3::Z));
 
-    let v = scene.view_factors_for_surface(0, 1_000_000)[1];
+    let v = scene.view_factors_for_surface(SurfaceId(0), 1_000_000)[1].mean;
 
     let x1 = (1. + x * x).sqrt();
     let y1 = (1. + y * y).sqrt();
@@ -176,7 +176,7 @@ pub fn unequal_disks(r1: f32, r2: f32) -> (f32, f32) {
 
     scene.add_surface(Surface::new_disk(Vec3::ZERO, r2, Vec3::X));
 
-    let v = scene.view_factors_for_surface(0, 1_000_000)[1];
+    let v = scene.view_factors_for_surface(SurfaceId(0), 1_000_000)[1].mean;
 
     let x = 1. + 1. / (r1 * r1) + r2 * r2 / (r1 * r1);
     let y = (x * x - 4. * r2 * r2 / (r1 * r1)).sqrt();
@@ -200,7 +200,7 @@ pub fn sphere_to_coaxial_cone(r: f32, s: f32, w: f32) -> (f32, f32) {
 
     scene.add_surface(Surface::new_cone((h + s) * Vec3::NEG_X, r, h, Vec3::X));
 
-    let v = scene.view_factors_for_surface(0, 1_000_000)[1];
+    let v = scene.view_factors_for_surface(SurfaceId(0), 1_000_000)[1].mean;
 
     let x = r / (1. + s + r / w.tan());
     let denom = (1. + x * x).sqrt();
@@ -224,15 +224,190 @@ pub fn coaxial_cone_to_sphere(r: f32, s: f32, w: f32) -> (f32, f32) {
 
     scene.add_surface(Surface::new_sphere(Vec3::X, 1.));
 
-    let v = scene.view_factors_for_surface(0, 1_000_000)[1];
+    let v = scene.view_factors_for_surface(SurfaceId(0), 1_000_000)[1].mean;
 
     let x = r / (1. + s + r / w.tan());
     let denom = (1. + x * x).sqrt();
 
-    let a_sphere = 4. * std::f32::consts::PI;
-    let a_cone = std::f32::consts::PI * r * (h * h + r * r).sqrt();
+    let areas = scene.areas();
     let v_an_sphere = 0.5 * (1. - 1. / denom);
-    let v_an = a_sphere / a_cone * v_an_sphere;
+    let v_an = areas[1] / areas[0] * v_an_sphere;
+
+    (v_an, v)
+}
+
+/// Analytical Source: http://webserver.dmt.upm.es/~isidoro/tc3/Radiation%20View%20factors.pdf
+/// Same as equal_rectangular_plates, but the receiving plate is split into two triangles
+/// x is width of plate divided by gap
+/// y is height of plate divided by gap
+pub fn rectangle_to_triangulated_plate(x: f32, y: f32) -> (f32, f32) {
+    let mut scene = Scene::default();
+
+    scene.add_surface(Surface::new_rectangle(Vec3::X, x, y, -Vec3::X, Vec3::Z));
+
+    // Corners of the receiving plate, wound counter-clockwise about +x
+    let a = Vec3::new(0., -y / 2., -x / 2.);
+    let b = Vec3::new(0., -y / 2., x / 2.);
+    let c = Vec3::new(0., y / 2., x / 2.);
+    let d = Vec3::new(0., y / 2., -x / 2.);
+
+    scene.add_surface(Surface::new_triangle(a, c, b));
+    scene.add_surface(Surface::new_triangle(a, d, c));
+
+    let v = scene.view_factors_for_surface(SurfaceId(0), 1_000_000);
+
+    let (v_an, _) = equal_rectangular_plates(x, y);
+
+    (v_an, v[1].mean + v[2].mean)
+}
+
+/// Analytical Source: http://webserver.dmt.upm.es/~isidoro/tc3/Radiation%20View%20factors.pdf
+/// Averages the opposite face view factors from the full view factor matrix of a unit cube enclosure
+pub fn cube_enclosure_opposite_faces() -> (f32, f32) {
+    let mut scene = Scene::default();
+
+    // Pairs of opposite faces are added one after the other, all facing inward
+    for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+        let axis_x = axis.any_orthonormal_vector();
+        scene.add_surface(Surface::new_rectangle(axis * 0.5, 1., 1., -axis, axis_x));
+        scene.add_surface(Surface::new_rectangle(-axis * 0.5, 1., 1., axis, axis_x));
+    }
+
+    let matrix = scene.view_factor_matrix(200_000);
+
+    // Every ray is absorbed somewhere in a closed black enclosure
+    for row in &matrix {
+        assert!((row.means().iter().sum::<f32>() - 1.).abs() < 1e-3);
+    }
+
+    let v = (0..6).map(|i| matrix[i][i ^ 1].mean).sum::<f32>() / 6.;
+
+    let (v_an, _) = equal_rectangular_plates(1., 1.);
+
+    (v_an, v)
+}
+
+/// Analytical Source: http://webserver.dmt.upm.es/~isidoro/tc3/Radiation%20View%20factors.pdf
+/// Same as sphere_in_cylinder, but only the upper half of one side of the cylinder is kept,
+/// which by symmetry sees a quarter of the sphere's rays
+/// h is ratio of cylinder radius to cylinder half-height
+pub fn sphere_in_partial_cylinder(h: f32) -> (f32, f32) {
+    let mut scene = Scene::default();
+
+    scene.add_surface(Surface::new_sphere(Vec3::ZERO, 0.1));
+
+    scene.add_surface(
+        Surface::new_cylinder(Vec3::ZERO, 1.0, 2. * h, Vec3::Z)
+            .set_sweep(0., std::f32::consts::PI, Vec3::X)
+            .set_z_limits(0., h),
+    );
+
+    let v = scene.view_factors_for_surface(SurfaceId(0), 1_000_000)[1].mean;
+    let v_an = 0.25 * h / (1.0f32 + h * h).sqrt();
+
+    (v_an, v)
+}
+
+/// Analytical Source: http://webserver.dmt.upm.es/~isidoro/tc3/Radiation%20View%20factors.pdf
+/// Same as unequal_disks, but the emitting disk is a pie slice, which sees the coaxial disk
+/// the same way the full disk does
+/// r1 is radius of disk 1 divided by gap
+/// r2 is radius of disk 2 divided by gap
+/// w is the angle of the slice (in radians)
+pub fn pie_slice_to_coaxial_disk(r1: f32, r2: f32, w: f32) -> (f32, f32) {
+    let mut scene = Scene::default();
+
+    scene.add_surface(Surface::new_disk(Vec3::X, r1, Vec3::NEG_X).set_sweep(0., w, Vec3::Y));
+
+    scene.add_surface(Surface::new_disk(Vec3::ZERO, r2, Vec3::X));
+
+    let v = scene.view_factors_for_surface(SurfaceId(0), 1_000_000)[1].mean;
+
+    let x = 1. + 1. / (r1 * r1) + r2 * r2 / (r1 * r1);
+    let y = (x * x - 4. * r2 * r2 / (r1 * r1)).sqrt();
+    let v_an = (x - y) / 2.;
+
+    (v_an, v)
+}
+
+/// Analytical Source: http://webserver.dmt.upm.es/~isidoro/tc3/Radiation%20View%20factors.pdf
+/// The base of a frustum sees its top through the unequal disks view factor, and the side
+/// wall sees everything else
+/// r is ratio of top radius to base radius
+/// h is ratio of frustum height to base radius
+pub fn frustum_base_to_side(r: f32, h: f32) -> (f32, f32) {
+    assert!(r > 0. && r < 1.);
+
+    let mut scene = Scene::default();
+
+    scene.add_surface(Surface::new_disk(Vec3::ZERO, 1., Vec3::Z));
+
+    // Full cone whose tip is above the top of the frustum
+    let cone_height = h / (1. - r);
+    scene.add_surface(
+        Surface::new_cone(Vec3::ZERO, 1., cone_height, Vec3::Z).set_truncation_height(h),
+    );
+
+    let v = scene.view_factors_for_surface(SurfaceId(0), 1_000_000)[1].mean;
+
+    let (r1, r2) = (1. / h, r / h);
+    let x = 1. + 1. / (r1 * r1) + r2 * r2 / (r1 * r1);
+    let y = (x * x - 4. * r2 * r2 / (r1 * r1)).sqrt();
+    let v_an = 1. - (x - y) / 2.;
+
+    (v_an, v)
+}
+
+/// Analytical Source: http://webserver.dmt.upm.es/~isidoro/tc3/Radiation%20View%20factors.pdf
+/// By superposition, a disk sees a coaxial annulus as the difference between two coaxial disks
+/// r1 is radius of the disk divided by gap
+/// r_in and r_out are the inner and outer radii of the annulus divided by gap
+pub fn disk_to_coaxial_annulus(r1: f32, r_in: f32, r_out: f32) -> (f32, f32) {
+    let mut scene = Scene::default();
+
+    scene.add_surface(Surface::new_disk(Vec3::X, r1, Vec3::NEG_X));
+
+    scene.add_surface(Surface::new_annulus(Vec3::ZERO, r_in, r_out, Vec3::X));
+
+    let v = scene.view_factors_for_surface(SurfaceId(0), 1_000_000)[1].mean;
+
+    let disks = |r2: f32| {
+        let x = 1. + 1. / (r1 * r1) + r2 * r2 / (r1 * r1);
+        let y = (x * x - 4. * r2 * r2 / (r1 * r1)).sqrt();
+        (x - y) / 2.
+    };
+    let v_an = disks(r_out) - disks(r_in);
+
+    (v_an, v)
+}
+
+/// Analytical Source: http://webserver.dmt.upm.es/~isidoro/tc3/Radiation%20View%20factors.pdf
+/// Same as sphere_in_cylinder, but inside the bore of a hollow cylinder primitive, whose end caps
+/// and outer wall must not be seen
+/// h is ratio of bore radius to cylinder half-height
+pub fn sphere_in_hollow_cylinder(h: f32) -> (f32, f32) {
+    let mut scene = Scene::default();
+
+    scene.add_surface(Surface::new_sphere(Vec3::ZERO, 0.1));
+
+    scene.add_primitive(HollowCylinder {
+        translation: Vec3::ZERO,
+        axis: Vec3::Z,
+        height: 2. * h,
+        inner_radius: 1.0,
+        outer_radius: 1.5,
+    });
+
+    let view_factors = scene.view_factors_for_surface(SurfaceId(0), 1_000_000);
+
+    // End caps and outer wall come before the inner wall, and can only be clipped by rounding
+    // where the caps meet the bore
+    for surface in 1..4 {
+        assert!(view_factors[surface].mean < 1e-5);
+    }
+
+    let v = view_factors[4].mean;
+    let v_an = h / (1.0f32 + h * h).sqrt();
 
     (v_an, v)
 }
@@ -0,0 +1,585 @@
+use std::fmt;
+
+use super::linear::solve_linear_system;
+
+/// Stefan-Boltzmann constant (W / m^2 K^4)
+pub const STEFAN_BOLTZMANN: f32 = 5.670_374e-8;
+
+/// Radiation nodes, which are surfaces or groups, coupled by radiation conductances, conductive
+/// links, and optionally radiation to space. Units follow the RADKs, e.g. areas in m^2 give watts
+/// with temperatures in kelvin.
+#[derive(Clone, Debug)]
+pub struct ThermalNetwork {
+    radks: Vec<Vec<f64>>,
+    // Conductance from each node to space, which absorbs what the other nodes do not
+    space_radks: Vec<f64>,
+    space_temperature: f64,
+    boundary_temperatures: Vec<Option<Schedule>>,
+    heat_loads: Vec<Schedule>,
+    // Heat capacity of each node (J / K), needed for transient solutions
+    capacitances: Vec<f64>,
+    // Pairs of nodes and the conductance between them (W / K)
+    conductors: Vec<(usize, usize, f64)>,
+}
+
+// Value over time, either constant or interpolated linearly between (time, value) points and held
+// constant beyond them
+#[derive(Clone, Debug)]
+enum Schedule {
+    Constant(f64),
+    Table(Vec<(f64, f64)>),
+}
+
+impl Schedule {
+    fn table(points: &[(f32, f32)]) -> Self {
+        assert!(!points.is_empty(), "Schedules must not be empty");
+        assert!(
+            points.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "Schedule times must be increasing"
+        );
+        Schedule::Table(
+            points
+                .iter()
+                .map(|&(time, value)| (time as f64, value as f64))
+                .collect(),
+        )
+    }
+
+    fn at(&self, time: f64) -> f64 {
+        match self {
+            Schedule::Constant(value) => *value,
+            Schedule::Table(points) => {
+                let i = points.partition_point(|&(t, _)| t <= time);
+                if i == 0 {
+                    points[0].1
+                } else if i == points.len() {
+                    points[i - 1].1
+                } else {
+                    let (t_0, v_0) = points[i - 1];
+                    let (t_1, v_1) = points[i];
+                    v_0 + (v_1 - v_0) * (time - t_0) / (t_1 - t_0)
+                }
+            }
+        }
+    }
+
+    fn minimum(&self) -> f64 {
+        match self {
+            Schedule::Constant(value) => *value,
+            Schedule::Table(points) => points.iter().map(|&(_, v)| v).fold(f64::MAX, f64::min),
+        }
+    }
+}
+
+/// Stopping criteria for the Newton iterations of the steady state solver
+#[derive(Clone, Copy, Debug)]
+pub struct SolverOptions {
+    /// Starting guess for every node without a boundary temperature, which must be positive
+    pub initial_temperature: f32,
+    /// Iterations stop once no temperature changes by more than this
+    pub tolerance: f32,
+    pub max_iterations: usize,
+}
+
+impl Default for SolverOptions {
+    fn default() -> Self {
+        Self {
+            initial_temperature: 300.0,
+            tolerance: 1e-4,
+            max_iterations: 100,
+        }
+    }
+}
+
+/// Time integration scheme for transient solutions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Integrator {
+    /// Explicit, and only stable for time steps shorter than the time constants of the nodes,
+    /// which is checked at every step
+    ForwardEuler,
+    /// Implicit trapezoidal rule, solved with Newton's method at every step
+    CrankNicolson,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TransientOptions {
+    pub integrator: Integrator,
+    pub time_step: f32,
+    pub duration: f32,
+    /// Newton iterations of implicit steps stop once no temperature changes by more than this
+    pub tolerance: f32,
+    pub max_iterations: usize,
+}
+
+impl TransientOptions {
+    pub fn new(integrator: Integrator, time_step: f32, duration: f32) -> Self {
+        Self {
+            integrator,
+            time_step,
+            duration,
+            tolerance: 1e-4,
+            max_iterations: 100,
+        }
+    }
+}
+
+/// Temperatures that balance the network
+#[derive(Clone, Debug)]
+pub struct SteadyState {
+    pub temperatures: Vec<f32>,
+    /// Heat each node takes in from outside the network beyond its load, which is what holds
+    /// boundary nodes at their temperature and zero for the others
+    pub boundary_heat_flows: Vec<f32>,
+    pub iterations: usize,
+}
+
+/// Temperatures of every node at the start and after each time step
+#[derive(Clone, Debug)]
+pub struct TemperatureHistory {
+    pub times: Vec<f32>,
+    /// Temperatures of every node at each of the times
+    pub temperatures: Vec<Vec<f32>>,
+}
+
+impl ThermalNetwork {
+    /// RADKs between every pair of nodes, which exchange only with each other until space is set
+    pub fn new(radks: &[Vec<f32>]) -> Self {
+        let n = radks.len();
+        assert!(radks.iter().all(|row| row.len() == n));
+
+        Self {
+            radks: radks
+                .iter()
+                .map(|row| row.iter().map(|&radk| radk as f64).collect())
+                .collect(),
+            space_radks: vec![0.0; n],
+            space_temperature: 0.0,
+            boundary_temperatures: vec![None; n],
+            heat_loads: vec![Schedule::Constant(0.0); n],
+            capacitances: vec![0.0; n],
+            conductors: Vec::new(),
+        }
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.heat_loads.len()
+    }
+
+    // Surroundings of an open scene, as a black sink at the given temperature. Whatever part of
+    // each node's emissivity times area is not coupled to other nodes radiates to space.
+    pub fn set_space(&mut self, emitting_areas: &[f32], temperature: f32) {
+        assert_eq!(emitting_areas.len(), self.num_nodes());
+        assert!(temperature >= 0.0, "Temperatures must not be negative");
+
+        self.space_radks = self
+            .radks
+            .iter()
+            .zip(emitting_areas)
+            .map(|(row, &area)| (area as f64 - row.iter().sum::<f64>()).max(0.0))
+            .collect();
+        self.space_temperature = temperature as f64;
+    }
+
+    // Holds a node at a fixed temperature, or frees it again when set to None
+    pub fn set_boundary_temperature(&mut self, node: usize, temperature: Option<f32>) {
+        assert!(
+            temperature.is_none_or(|temperature| temperature >= 0.0),
+            "Temperatures must not be negative"
+        );
+        self.boundary_temperatures[node] =
+            temperature.map(|temperature| Schedule::Constant(temperature as f64));
+    }
+
+    // Holds a node at a temperature that varies over time, from (time, temperature) points
+    pub fn set_boundary_temperature_schedule(&mut self, node: usize, points: &[(f32, f32)]) {
+        let schedule = Schedule::table(points);
+        assert!(
+            schedule.minimum() >= 0.0,
+            "Temperatures must not be negative"
+        );
+        self.boundary_temperatures[node] = Some(schedule);
+    }
+
+    // Heat dissipated in a node, e.g. by electronics or absorbed sunlight
+    pub fn set_heat_load(&mut self, node: usize, heat_load: f32) {
+        self.heat_loads[node] = Schedule::Constant(heat_load as f64);
+    }
+
+    // Heat load that varies over time, from (time, heat load) points
+    pub fn set_heat_load_schedule(&mut self, node: usize, points: &[(f32, f32)]) {
+        self.heat_loads[node] = Schedule::table(points);
+    }
+
+    // Heat capacity of a node, which every node without a boundary temperature needs for
+    // transient solutions
+    pub fn set_capacitance(&mut self, node: usize, capacitance: f32) {
+        assert!(capacitance >= 0.0, "Capacitance must not be negative");
+        self.capacitances[node] = capacitance as f64;
+    }
+
+    // Conductive link between two nodes
+    pub fn add_conductor(&mut self, a: usize, b: usize, conductance: f32) {
+        assert!(a != b && a.max(b) < self.num_nodes());
+        assert!(conductance >= 0.0, "Conductance must not be negative");
+        self.conductors.push((a, b, conductance as f64));
+    }
+
+    // Solves the sigma T^4 balance for the nodes without boundary temperatures using Newton's
+    // method, with schedules taken at time zero
+    pub fn solve_steady_state(&self, options: SolverOptions) -> Result<SteadyState, ThermalError> {
+        // Radiation has no slope at zero, so Newton's method cannot start from there
+        assert!(
+            options.initial_temperature > 0.0,
+            "Initial temperature must be positive"
+        );
+
+        let mut temperatures: Vec<f64> = self
+            .boundary_temperatures_at(0.0)
+            .iter()
+            .map(|boundary| boundary.unwrap_or(options.initial_temperature as f64))
+            .collect();
+        let heat_loads = self.heat_loads_at(0.0);
+
+        let iterations = self.balance(
+            &mut temperatures,
+            1.0,
+            &vec![0.0; self.num_nodes()],
+            &heat_loads,
+            options.tolerance as f64,
+            options.max_iterations,
+        )?;
+
+        let outflows = self.heat_outflows(&temperatures);
+        let boundary_heat_flows = (0..self.num_nodes())
+            .map(|node| match self.boundary_temperatures[node] {
+                Some(_) => (outflows[node] - heat_loads[node]) as f32,
+                None => 0.0,
+            })
+            .collect();
+
+        Ok(SteadyState {
+            temperatures: temperatures.iter().map(|&t| t as f32).collect(),
+            boundary_heat_flows,
+            iterations,
+        })
+    }
+
+    // Steps the temperatures of the nodes from their initial values, which are replaced by the
+    // boundary temperature of nodes that have one
+    pub fn solve_transient(
+        &self,
+        initial_temperatures: &[f32],
+        options: TransientOptions,
+    ) -> Result<TemperatureHistory, ThermalError> {
+        assert_eq!(initial_temperatures.len(), self.num_nodes());
+        assert!(
+            initial_temperatures.iter().all(|&t| t >= 0.0),
+            "Temperatures must not be negative"
+        );
+        assert!(options.time_step > 0.0, "Time step must be positive");
+        for node in 0..self.num_nodes() {
+            assert!(
+                self.boundary_temperatures[node].is_some() || self.capacitances[node] > 0.0,
+                "node {node} needs a capacitance for transient solutions"
+            );
+        }
+
+        let mut temperatures: Vec<f64> = initial_temperatures.iter().map(|&t| t as f64).collect();
+        self.apply_boundary_temperatures(&mut temperatures, 0.0);
+
+        let duration = options.duration as f64;
+        let num_steps = (duration / options.time_step as f64 - 1e-6).ceil().max(0.0) as usize;
+        let mut history = TemperatureHistory {
+            times: vec![0.0],
+            temperatures: vec![temperatures.iter().map(|&t| t as f32).collect()],
+        };
+
+        let mut time = 0.0;
+        for step in 1..=num_steps {
+            // The last step is shortened to end at the duration
+            let next_time = (step as f64 * options.time_step as f64).min(duration);
+            let time_step = next_time - time;
+
+            let heat_loads = self.heat_loads_at(time);
+            let outflows = self.heat_outflows(&temperatures);
+            let mut next = temperatures.clone();
+            self.apply_boundary_temperatures(&mut next, next_time);
+
+            match options.integrator {
+                Integrator::ForwardEuler => {
+                    self.check_explicit_step(&temperatures, time_step, time)?;
+                    for node in self.free_nodes() {
+                        next[node] = temperatures[node]
+                            + time_step * (heat_loads[node] - outflows[node])
+                                / self.capacitances[node];
+
+                        // Nonlinear radiation can still overshoot a step the check allows
+                        if next[node] < 0.0 {
+                            return Err(ThermalError::NegativeTemperature {
+                                node,
+                                time: next_time as f32,
+                            });
+                        }
+                    }
+                }
+                Integrator::CrankNicolson => {
+                    // C (T' - T) / dt = (Q - q(T) + Q' - q(T')) / 2, solved for T'
+                    let next_heat_loads = self.heat_loads_at(next_time);
+                    let diagonal: Vec<f64> =
+                        self.capacitances.iter().map(|c| c / time_step).collect();
+                    let targets: Vec<f64> = (0..self.num_nodes())
+                        .map(|node| {
+                            diagonal[node] * temperatures[node]
+                                + 0.5 * (heat_loads[node] - outflows[node])
+                                + 0.5 * next_heat_loads[node]
+                        })
+                        .collect();
+
+                    self.balance(
+                        &mut next,
+                        0.5,
+                        &diagonal,
+                        &targets,
+                        options.tolerance as f64,
+                        options.max_iterations,
+                    )?;
+                }
+            }
+
+            temperatures = next;
+            time = next_time;
+            history.times.push(time as f32);
+            history
+                .temperatures
+                .push(temperatures.iter().map(|&t| t as f32).collect());
+        }
+
+        Ok(history)
+    }
+
+    // Forward Euler steps are stable and keep linear networks from overshooting while each is no
+    // longer than the time constant C / G of every free node, where G is how fast the node's
+    // outflow grows with its temperature
+    fn check_explicit_step(
+        &self,
+        temperatures: &[f64],
+        time_step: f64,
+        time: f64,
+    ) -> Result<(), ThermalError> {
+        let jacobian = self.outflow_jacobian(temperatures);
+        for node in self.free_nodes() {
+            let max_time_step = self.capacitances[node] / jacobian[node][node];
+            if time_step > max_time_step {
+                return Err(ThermalError::UnstableTimeStep {
+                    node,
+                    time: time as f32,
+                    max_time_step: max_time_step as f32,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn free_nodes(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.num_nodes()).filter(|&node| self.boundary_temperatures[node].is_none())
+    }
+
+    fn boundary_temperatures_at(&self, time: f64) -> Vec<Option<f64>> {
+        self.boundary_temperatures
+            .iter()
+            .map(|boundary| boundary.as_ref().map(|schedule| schedule.at(time)))
+            .collect()
+    }
+
+    fn apply_boundary_temperatures(&self, temperatures: &mut [f64], time: f64) {
+        for (temperature, boundary) in temperatures.iter_mut().zip(&self.boundary_temperatures) {
+            if let Some(schedule) = boundary {
+                *temperature = schedule.at(time);
+            }
+        }
+    }
+
+    fn heat_loads_at(&self, time: f64) -> Vec<f64> {
+        self.heat_loads.iter().map(|load| load.at(time)).collect()
+    }
+
+    // Newton's method for the free temperatures that satisfy
+    // outflow_weight * q_i(T) + diagonal_i * T_i = target_i, where q is the heat outflow, starting
+    // from and updating temperatures. Returns the number of iterations, or an error if a free node
+    // has no path to a boundary node or space, which leaves its temperature undetermined.
+    fn balance(
+        &self,
+        temperatures: &mut [f64],
+        outflow_weight: f64,
+        diagonal: &[f64],
+        targets: &[f64],
+        tolerance: f64,
+        max_iterations: usize,
+    ) -> Result<usize, ThermalError> {
+        let free: Vec<usize> = self.free_nodes().collect();
+
+        let mut iterations = 0;
+        loop {
+            let outflows = self.heat_outflows(temperatures);
+            let jacobian = self.outflow_jacobian(temperatures);
+
+            // Change in the free temperatures that balances the linearized network
+            let system = free
+                .iter()
+                .map(|&i| {
+                    let mut row: Vec<f64> = free
+                        .iter()
+                        .map(|&k| outflow_weight * jacobian[i][k])
+                        .collect();
+                    row[free.iter().position(|&k| k == i).unwrap()] += diagonal[i];
+                    row.push(
+                        targets[i] - outflow_weight * outflows[i] - diagonal[i] * temperatures[i],
+                    );
+                    row
+                })
+                .collect();
+            let solution = solve_linear_system(system);
+            if let Some(&column) = solution.undetermined.first() {
+                return Err(ThermalError::NoHeatPath { node: free[column] });
+            }
+            let changes = solution.values;
+
+            // Limit steps so temperatures stay positive, where T^4 has a single root
+            let mut max_change = 0.0f64;
+            for (&node, change) in free.iter().zip(changes) {
+                let temperature = (temperatures[node] + change).max(0.5 * temperatures[node]);
+                max_change = max_change.max((temperature - temperatures[node]).abs());
+                temperatures[node] = temperature;
+            }
+            iterations += 1;
+
+            if max_change <= tolerance {
+                return Ok(iterations);
+            }
+            if iterations >= max_iterations {
+                return Err(ThermalError::NotConverged {
+                    iterations,
+                    max_change: max_change as f32,
+                });
+            }
+        }
+    }
+
+    // Net heat leaving each node through radiation and conduction
+    pub(crate) fn heat_outflows(&self, temperatures: &[f64]) -> Vec<f64> {
+        let sigma = STEFAN_BOLTZMANN as f64;
+        let emissive_powers: Vec<f64> = temperatures.iter().map(|t| sigma * t.powi(4)).collect();
+        let space_power = sigma * self.space_temperature.powi(4);
+
+        // Energy a node absorbs from itself cancels out
+        let mut outflows: Vec<f64> = (0..self.num_nodes())
+            .map(|i| {
+                let exchange: f64 = (0..self.num_nodes())
+                    .filter(|&j| j != i)
+                    .map(|j| {
+                        self.radks[i][j] * emissive_powers[i]
+                            - self.radks[j][i] * emissive_powers[j]
+                    })
+                    .sum();
+                exchange + self.space_radks[i] * (emissive_powers[i] - space_power)
+            })
+            .collect();
+
+        for &(a, b, conductance) in &self.conductors {
+            let flow = conductance * (temperatures[a] - temperatures[b]);
+            outflows[a] += flow;
+            outflows[b] -= flow;
+        }
+        outflows
+    }
+
+    // Derivative of each node's heat outflow with respect to every temperature
+    pub(crate) fn outflow_jacobian(&self, temperatures: &[f64]) -> Vec<Vec<f64>> {
+        let n = self.num_nodes();
+        let sigma = STEFAN_BOLTZMANN as f64;
+        let slopes: Vec<f64> = temperatures
+            .iter()
+            .map(|t| 4.0 * sigma * t.powi(3))
+            .collect();
+
+        let mut jacobian: Vec<Vec<f64>> = (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|k| {
+                        if k == i {
+                            let to_others: f64 =
+                                (0..n).filter(|&j| j != i).map(|j| self.radks[i][j]).sum();
+                            (to_others + self.space_radks[i]) * slopes[i]
+                        } else {
+                            -self.radks[k][i] * slopes[k]
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        for &(a, b, conductance) in &self.conductors {
+            jacobian[a][a] += conductance;
+            jacobian[b][b] += conductance;
+            jacobian[a][b] -= conductance;
+            jacobian[b][a] -= conductance;
+        }
+        jacobian
+    }
+}
+
+#[derive(Debug)]
+pub enum ThermalError {
+    NotConverged {
+        iterations: usize,
+        max_change: f32,
+    },
+    /// A free node, or a set of free nodes coupled only to each other, is not coupled to a
+    /// boundary node or space, so nothing sets its temperature
+    NoHeatPath {
+        node: usize,
+    },
+    /// A forward Euler step is longer than the time constant of a node
+    UnstableTimeStep {
+        node: usize,
+        time: f32,
+        max_time_step: f32,
+    },
+    NegativeTemperature {
+        node: usize,
+        time: f32,
+    },
+}
+
+impl fmt::Display for ThermalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThermalError::NotConverged {
+                iterations,
+                max_change,
+            } => write!(
+                f,
+                "temperatures still changed by {max_change} after {iterations} iterations"
+            ),
+            ThermalError::NoHeatPath { node } => write!(
+                f,
+                "node {node} is not coupled to a boundary node or space, so its temperature is \
+                 undetermined"
+            ),
+            ThermalError::UnstableTimeStep {
+                node,
+                time,
+                max_time_step,
+            } => write!(
+                f,
+                "forward Euler step at {time} s is unstable for node {node}, which needs steps \
+                 of at most {max_time_step} s"
+            ),
+            ThermalError::NegativeTemperature { node, time } => {
+                write!(f, "node {node} fell below 0 K at {time} s")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ThermalError {}
@@ -8,6 +8,9 @@ pub struct TraceRecord {
     origin: Vec3,
     last_ray: Ray,
     pub entries: Vec<TraceRecordEntry>,
+    // Energy absorbed at each entry in every spectral band, when a ray carries more than one
+    band_energies: Vec<f32>,
+    num_bands: usize,
 }
 
 impl TraceRecord {
@@ -17,16 +20,33 @@ impl TraceRecord {
             origin: ray.origin.0,
             last_ray: ray,
             entries: Vec::new(),
+            band_energies: Vec::new(),
+            num_bands: 1,
         }
     }
 
-    pub fn add_entry(&mut self, ray: Ray, hit_record: HitRecord, energy_absorbed: f32) {
+    // Energy absorbed in each band, where a ray carrying one band records only energy_absorbed
+    pub fn add_entry(&mut self, ray: Ray, hit_record: HitRecord, energies_absorbed: &[f32]) {
         self.last_ray = ray;
         self.entries.push(TraceRecordEntry {
             surface_id: hit_record.surface_id,
-            energy_absorbed,
+            energy_absorbed: energies_absorbed[0],
             point: hit_record.hit.position,
-        })
+        });
+
+        self.num_bands = energies_absorbed.len();
+        if self.num_bands > 1 {
+            self.band_energies.extend_from_slice(energies_absorbed);
+        }
+    }
+
+    // Energy absorbed in a spectral band at the entry with the given index
+    pub fn band_energy_absorbed(&self, entry: usize, band: usize) -> f32 {
+        if self.num_bands > 1 {
+            self.band_energies[entry * self.num_bands + band]
+        } else {
+            self.entries[entry].energy_absorbed
+        }
     }
 
     pub fn terminate_early(&mut self) {
@@ -36,8 +56,9 @@ impl TraceRecord {
 
 pub struct TraceRecordEntry {
     pub surface_id: usize,
+    // Energy absorbed in the first band, for rays that carry several
     pub energy_absorbed: f32,
-    point: Point,
+    pub point: Point,
 }
 
 // TODO: Very hacky, needs to be refactored to be more readable
@@ -16,10 +16,17 @@ impl Transform {
         let scale = Mat4::from_scale(scale);
 
         let rotation_1 = Mat4::from_quat(Quat::from_rotation_arc(Vec3::Z, axis_z));
-        let rotation_2 = Mat4::from_quat(Quat::from_rotation_arc(
-            rotation_1.transform_vector3(Vec3::X),
-            axis_x,
-        ));
+
+        // A half turn must be about axis_z, otherwise the arc could flip the normal
+        let rotated_x = rotation_1.transform_vector3(Vec3::X);
+        let rotation_2 = if rotated_x.dot(axis_x.normalize()) < -0.9999 {
+            Mat4::from_quat(Quat::from_axis_angle(
+                axis_z.normalize(),
+                std::f32::consts::PI,
+            ))
+        } else {
+            Mat4::from_quat(Quat::from_rotation_arc(rotated_x, axis_x))
+        };
         let rotation = rotation_2 * rotation_1;
 
         let m = translation * rotation * scale;
@@ -30,10 +37,36 @@ impl Transform {
             m_inv_trans: Mat3::from_mat4(m).inverse().transpose(),
         }
     }
+
+    // Maps the local x, y, and z axes directly onto the given vectors, allowing shear
+    pub fn from_basis(translation: Vec3, axis_x: Vec3, axis_y: Vec3, axis_z: Vec3) -> Self {
+        let m = Mat4::from_cols(
+            axis_x.extend(0.0),
+            axis_y.extend(0.0),
+            axis_z.extend(0.0),
+            translation.extend(1.0),
+        );
+
+        Self {
+            m,
+            m_inv: m.inverse(),
+            m_inv_trans: Mat3::from_mat4(m).inverse().transpose(),
+        }
+    }
 }
 
 // Transformations
 impl Transform {
+    // Whether the local axes stay perpendicular in world space, so the transform has no shear
+    pub fn is_orthogonal(&self) -> bool {
+        let (x, y, z) = (
+            self.m.x_axis.truncate().normalize(),
+            self.m.y_axis.truncate().normalize(),
+            self.m.z_axis.truncate().normalize(),
+        );
+        x.dot(y).abs() < 1e-5 && y.dot(z).abs() < 1e-5 && z.dot(x).abs() < 1e-5
+    }
+
     pub fn normal_local_to_world(&self, normal: Normal) -> Normal {
         Normal::new_from_unnormalized(self.m_inv_trans * normal.0)
     }
@@ -0,0 +1,368 @@
+use std::ops::Index;
+
+/// Monte Carlo estimate of the view factor to a single surface
+#[derive(Clone, Debug)]
+pub struct ViewFactorEstimate {
+    pub mean: f32,
+    /// Sample variance of the energy a single ray deposits on the surface
+    pub variance: f32,
+    /// Estimated from the variance for independent rays, or from the spread between replicate
+    /// sequences for scrambled Sobol points
+    pub standard_error: f32,
+    /// Degrees of freedom of the standard error: one less than the number of rays, or than the
+    /// number of replicate sequences for scrambled Sobol points
+    pub degrees_of_freedom: usize,
+}
+
+// Beyond this the Student-t quantiles are within about 0.2% of the normal ones
+const NORMAL_DEGREES_OF_FREEDOM: usize = 1000;
+
+impl ViewFactorEstimate {
+    /// Two sided confidence interval for the mean, e.g. confidence = 0.95 for a 95% interval.
+    /// Standard errors from few degrees of freedom, such as the spread between replicate
+    /// sequences, are scaled by a Student-t quantile instead of a normal one.
+    pub fn confidence_interval(&self, confidence: f32) -> (f32, f32) {
+        assert!(confidence > 0.0 && confidence < 1.0);
+
+        let p = 0.5 + 0.5 * confidence as f64;
+        let quantile = match self.degrees_of_freedom {
+            // A single ray has no spread, so its interval has no width
+            0 => 0.0,
+            dof if dof > NORMAL_DEGREES_OF_FREEDOM => standard_normal_quantile(p),
+            dof => student_t_quantile(p, dof),
+        };
+        let half_width = quantile as f32 * self.standard_error;
+
+        (self.mean - half_width, self.mean + half_width)
+    }
+
+    /// Standard error divided by the mean, or infinity if nothing has been hit
+    pub fn relative_standard_error(&self) -> f32 {
+        if self.mean > 0.0 {
+            self.standard_error / self.mean
+        } else {
+            f32::INFINITY
+        }
+    }
+}
+
+/// View factors from one surface or group to every surface or group in a scene
+#[derive(Clone, Debug)]
+pub struct ViewFactors {
+    pub num_rays: usize,
+    pub estimates: Vec<ViewFactorEstimate>,
+    /// View factors to each bin of every surface, empty for surfaces with a single bin
+    pub bins: Vec<Vec<ViewFactorEstimate>>,
+}
+
+impl ViewFactors {
+    pub fn means(&self) -> Vec<f32> {
+        self.estimates
+            .iter()
+            .map(|estimate| estimate.mean)
+            .collect()
+    }
+
+    pub fn confidence_intervals(&self, confidence: f32) -> Vec<(f32, f32)> {
+        self.estimates
+            .iter()
+            .map(|estimate| estimate.confidence_interval(confidence))
+            .collect()
+    }
+}
+
+impl Index<usize> for ViewFactors {
+    type Output = ViewFactorEstimate;
+
+    fn index(&self, surface: usize) -> &ViewFactorEstimate {
+        &self.estimates[surface]
+    }
+}
+
+/// Stopping criteria for adaptive view factor computation
+#[derive(Clone, Debug)]
+pub struct ConvergenceTarget {
+    /// Largest acceptable standard error relative to the view factor
+    pub relative_standard_error: f32,
+    /// View factors no larger than this do not need to converge, so with zero only surfaces that
+    /// are never hit are left out
+    pub min_view_factor: f32,
+    /// Tracing stops after this many rays even if the target is not met
+    pub max_rays: usize,
+}
+
+impl ConvergenceTarget {
+    pub fn is_met(&self, view_factors: &ViewFactors) -> bool {
+        view_factors
+            .estimates
+            .iter()
+            .filter(|estimate| estimate.mean > self.min_view_factor)
+            .all(|estimate| estimate.relative_standard_error() <= self.relative_standard_error)
+    }
+
+    // Multiple of the current ray count predicted to meet the target, with a little margin
+    pub(crate) fn rays_needed_factor(&self, view_factors: &ViewFactors) -> f32 {
+        let worst_ratio = view_factors
+            .estimates
+            .iter()
+            .filter(|estimate| estimate.mean > self.min_view_factor)
+            .map(|estimate| estimate.relative_standard_error() / self.relative_standard_error)
+            .fold(1.0, f32::max);
+
+        1.1 * worst_ratio * worst_ratio
+    }
+}
+
+/// Running sums of the energy each ray deposits in every slot. The first slots are the surfaces or
+/// groups being reported on, followed by the bins of every binned surface.
+#[derive(Clone)]
+pub(crate) struct Tally {
+    num_rays: usize,
+    num_targets: usize,
+    // Number of bins of each surface, or zero if it is not binned
+    bin_counts: Vec<usize>,
+    sums: Vec<f64>,
+    sums_squared: Vec<f64>,
+    // Rays and sums of each replicate sequence, slots within replicates, when there is more than one
+    num_replicates: usize,
+    replicate_rays: Vec<usize>,
+    replicate_sums: Vec<f64>,
+    // Scratch space for combining multiple hits on the same surface by a single ray
+    ray_energy: Vec<f64>,
+    touched: Vec<usize>,
+}
+
+impl Tally {
+    pub fn new(num_targets: usize, bin_counts: &[usize], num_replicates: usize) -> Self {
+        let num_slots = num_targets + bin_counts.iter().sum::<usize>();
+        let replicate_slots = if num_replicates > 1 {
+            num_replicates * num_slots
+        } else {
+            0
+        };
+
+        Self {
+            num_rays: 0,
+            num_targets,
+            bin_counts: bin_counts.to_vec(),
+            sums: vec![0.0; num_slots],
+            sums_squared: vec![0.0; num_slots],
+            num_replicates,
+            replicate_rays: vec![0; num_replicates],
+            replicate_sums: vec![0.0; replicate_slots],
+            ray_energy: vec![0.0; num_slots],
+            touched: Vec::new(),
+        }
+    }
+
+    pub fn num_rays(&self) -> usize {
+        self.num_rays
+    }
+
+    // Energy a single ray deposits in each slot, where a slot can appear more than once. Rays are
+    // indexed from the first ray traced from the emitter, which sets their replicate.
+    pub fn add_deposits(&mut self, index: usize, deposits: impl IntoIterator<Item = (usize, f32)>) {
+        self.num_rays += 1;
+        let replicate = index % self.num_replicates;
+        self.replicate_rays[replicate] += 1;
+        let num_slots = self.sums.len();
+
+        // A ray can be absorbed by the same surface more than once, so combine before squaring
+        for (slot, energy) in deposits {
+            if self.ray_energy[slot] == 0.0 {
+                self.touched.push(slot);
+            }
+            self.ray_energy[slot] += energy as f64;
+        }
+
+        for slot in self.touched.drain(..) {
+            let energy = std::mem::take(&mut self.ray_energy[slot]);
+            self.sums[slot] += energy;
+            self.sums_squared[slot] += energy * energy;
+            if self.num_replicates > 1 {
+                self.replicate_sums[replicate * num_slots + slot] += energy;
+            }
+        }
+    }
+
+    pub fn merge(mut self, other: Tally) -> Self {
+        self.num_rays += other.num_rays;
+        for (sum, other_sum) in self.sums.iter_mut().zip(other.sums) {
+            *sum += other_sum;
+        }
+        for (sum, other_sum) in self.sums_squared.iter_mut().zip(other.sums_squared) {
+            *sum += other_sum;
+        }
+        for (rays, other_rays) in self.replicate_rays.iter_mut().zip(other.replicate_rays) {
+            *rays += other_rays;
+        }
+        for (sum, other_sum) in self.replicate_sums.iter_mut().zip(other.replicate_sums) {
+            *sum += other_sum;
+        }
+        self
+    }
+
+    pub fn view_factors(&self) -> ViewFactors {
+        let n = self.num_rays as f64;
+
+        let mut estimates = self
+            .sums
+            .iter()
+            .zip(&self.sums_squared)
+            .enumerate()
+            .map(|(slot, (&sum, &sum_squared))| {
+                let mean = sum / n;
+                let variance = if self.num_rays > 1 {
+                    ((sum_squared - n * mean * mean) / (n - 1.0)).max(0.0)
+                } else {
+                    0.0
+                };
+
+                let (standard_error, degrees_of_freedom) = if self.num_replicates > 1 {
+                    (self.replicate_standard_error(slot), self.num_replicates - 1)
+                } else {
+                    ((variance / n).sqrt(), self.num_rays.saturating_sub(1))
+                };
+
+                ViewFactorEstimate {
+                    mean: mean as f32,
+                    variance: variance as f32,
+                    standard_error: standard_error as f32,
+                    degrees_of_freedom,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut bin_estimates = estimates.split_off(self.num_targets).into_iter();
+        let bins = self
+            .bin_counts
+            .iter()
+            .map(|&count| bin_estimates.by_ref().take(count).collect())
+            .collect();
+
+        ViewFactors {
+            num_rays: self.num_rays,
+            estimates,
+            bins,
+        }
+    }
+
+    // Randomized quasi-Monte Carlo error from the spread of the replicate means, since rays within
+    // one sequence are not independent. Infinite until every replicate has rays.
+    fn replicate_standard_error(&self, slot: usize) -> f64 {
+        if self.replicate_rays.contains(&0) {
+            return f64::INFINITY;
+        }
+
+        let num_slots = self.sums.len();
+        let means: Vec<f64> = self
+            .replicate_rays
+            .iter()
+            .enumerate()
+            .map(|(replicate, &rays)| {
+                self.replicate_sums[replicate * num_slots + slot] / rays as f64
+            })
+            .collect();
+
+        let r = self.num_replicates as f64;
+        let mean = means.iter().sum::<f64>() / r;
+        let variance = means.iter().map(|m| (m - mean).powi(2)).sum::<f64>() / (r - 1.0);
+        (variance / r).sqrt()
+    }
+}
+
+// Inverse of the standard normal CDF, accurate to about 1e-9
+// Source: Peter Acklam, "An algorithm for computing the inverse normal cumulative distribution function"
+fn standard_normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+
+    if p < P_LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - P_LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+// Quantile of Student's t distribution with the given degrees of freedom, for p above one half.
+// Bisects the closed form of P(|T| < t) for integer degrees of freedom in terms of
+// theta = atan(t / sqrt(dof)).
+// Source: Abramowitz and Stegun, 26.7.3 and 26.7.4
+fn student_t_quantile(p: f64, degrees_of_freedom: usize) -> f64 {
+    use std::f64::consts::{FRAC_2_PI, FRAC_PI_2};
+
+    let central = |theta: f64| {
+        let (sin, cos) = theta.sin_cos();
+        let cos_squared = cos * cos;
+
+        // Series in powers of cos^2 up to cos^(dof - 2), starting from cos for odd degrees
+        let (mut term, mut k) = if degrees_of_freedom % 2 == 1 {
+            (cos, 1)
+        } else {
+            (1.0, 0)
+        };
+        let mut sum = 0.0;
+        while k + 2 <= degrees_of_freedom {
+            sum += term;
+            term *= cos_squared * (k + 1) as f64 / (k + 2) as f64;
+            k += 2;
+        }
+
+        if degrees_of_freedom % 2 == 1 {
+            FRAC_2_PI * (theta + sin * sum)
+        } else {
+            sin * sum
+        }
+    };
+
+    let target = 2.0 * p - 1.0;
+    let (mut low, mut high) = (0.0, FRAC_PI_2);
+    for _ in 0..100 {
+        let mid = 0.5 * (low + high);
+        if central(mid) < target {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    (degrees_of_freedom as f64).sqrt() * (0.5 * (low + high)).tan()
+}